@@ -0,0 +1,20 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Generates the wire format's code from schema/packet.capnp. That needs the `capnp` schema compiler,
+/// without it the generated copy in schema/ is used instead.
+fn main() {
+    println!("cargo:rerun-if-changed=schema/packet.capnp");
+    println!("cargo:rerun-if-changed=schema/packet_capnp.rs");
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR for build scripts"));
+    let generated = capnpc::CompilerCommand::new()
+        .src_prefix("schema")
+        .file("schema/packet.capnp")
+        .output_path(&out_dir)
+        .run();
+    if generated.is_err() {
+        fs::copy("schema/packet_capnp.rs", out_dir.join("packet_capnp.rs")).expect("could not copy schema/packet_capnp.rs");
+    }
+}
@@ -0,0 +1,56 @@
+use std::env;
+use std::net::TcpListener;
+use better_term::style::Color;
+use mini_mmo_client::mock_server::{Fixture, MockServer};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:2277";
+
+fn usage() {
+    println!("Usage: mock_server [fixture.toml] [--bind <address>]");
+    println!("Runs a local stand-in for the game server, see mock_server.example.toml for the fixture format.");
+}
+
+fn main() {
+    let mut fixture_path = None;
+    let mut address = DEFAULT_ADDRESS.to_string();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" => match args.next() {
+                Some(a) => address = a,
+                None => {
+                    usage();
+                    return;
+                }
+            },
+            "-h" | "--help" => {
+                usage();
+                return;
+            }
+            _ => fixture_path = Some(arg),
+        }
+    }
+
+    let fixture = match fixture_path {
+        Some(path) => match Fixture::load(path) {
+            Ok(fixture) => fixture,
+            Err(e) => {
+                eprintln!("{}{}", Color::Red, e);
+                return;
+            }
+        },
+        None => Fixture::default(),
+    };
+
+    let listener = match TcpListener::bind(address.as_str()) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("{}Failed to listen on {}: {}", Color::Red, address, e);
+            return;
+        }
+    };
+
+    println!("Mock server for version {} listening on {}", fixture.version, address);
+    MockServer::new(fixture).on_log(|line| println!("{}", line)).serve(listener);
+}
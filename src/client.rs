@@ -0,0 +1,243 @@
+use std::io;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::network::connection::Connection;
+use crate::network::error::NetworkError;
+use crate::network::event_io::{write_event_error, write_event_message};
+use crate::network::event_reader::{spawn_event_reader, SharedWriter};
+use crate::network::handshake::{Handshake, HandshakeError, HandshakeState, LoggedIn};
+use crate::network::login_data::LoginData;
+use crate::network::server_event::ServerEvent;
+use crate::network::tls::{TlsConfig, FingerprintStore};
+use crate::network::transport::Transport;
+
+type EventResult = Result<ServerEvent, NetworkError>;
+
+enum ClientState<S: Transport> {
+    /// connected and version checked, waiting for a login
+    Entry(Handshake<S>),
+    /// logged in, events are read on a background thread.
+    /// Once they are forwarded, `forwarded` closes when the last one has been passed on,
+    /// and nothing more is passed on after `closed` is set
+    Session { writer: SharedWriter<S>, events: Option<Receiver<EventResult>>, forwarded: Option<Receiver<()>>, closed: Arc<AtomicBool> },
+    Closed,
+}
+
+/// A connection to the game server.
+///
+/// Connecting checks the version, after which `login` (or `resume`) can be retried until it succeeds.
+/// Once logged in, keepalives are answered in the background and everything else is returned by `next_event`.
+pub struct Client<S: Transport = Connection> {
+    state: ClientState<S>,
+    resume_token: Option<String>,
+}
+
+impl Client<Connection> {
+    /// Connects to `host:port`, over TLS if `tls` is set, and checks that the server runs this client's version
+    pub fn connect(host: &str, port: u16, tls: Option<(&TlsConfig, FingerprintStore)>) -> Result<Self, HandshakeError> {
+        Self::with_version(Handshake::connect(host, port, tls)?, crate::VERSION)
+    }
+}
+
+impl<S: Transport> Client<S> {
+    /// Runs the protocol over an already connected transport, checking the version first
+    pub fn new(stream: S) -> Result<Self, HandshakeError> {
+        Self::with_version(Handshake::new(stream), crate::VERSION)
+    }
+
+    fn with_version(mut handshake: Handshake<S>, version: &str) -> Result<Self, HandshakeError> {
+        handshake.check_version(version)?;
+        Ok(Self {
+            state: ClientState::Entry(handshake),
+            resume_token: None,
+        })
+    }
+
+    /// Logs in, or signs up if `login_data.signup` is set.
+    /// A rejected attempt (`HandshakeError::LoginRejected`) can be retried.
+    pub fn login(&mut self, login_data: LoginData) -> Result<LoggedIn, HandshakeError> {
+        let logged_in = self.handshake()?.login(login_data)?;
+        self.start_session(logged_in)
+    }
+
+    /// Continues a previous session with its resume token, see `resume_token`
+    pub fn resume(&mut self, resume_token: &str) -> Result<LoggedIn, HandshakeError> {
+        let logged_in = self.handshake()?.resume(resume_token)?;
+        self.start_session(logged_in)
+    }
+
+    /// The token to resume the current session with after reconnecting, if the server issued one
+    pub fn resume_token(&self) -> Option<&str> {
+        self.resume_token.as_deref()
+    }
+
+    pub fn is_logged_in(&self) -> bool {
+        matches!(self.state, ClientState::Session { .. })
+    }
+
+    fn handshake(&mut self) -> Result<&mut Handshake<S>, HandshakeError> {
+        match &mut self.state {
+            ClientState::Entry(handshake) => Ok(handshake),
+            ClientState::Session { .. } => Err(HandshakeError::InvalidState { expected: HandshakeState::VersionAccepted, actual: HandshakeState::LoggedIn }),
+            ClientState::Closed => Err(HandshakeError::InvalidState { expected: HandshakeState::VersionAccepted, actual: HandshakeState::Failed }),
+        }
+    }
+
+    fn start_session(&mut self, logged_in: LoggedIn) -> Result<LoggedIn, HandshakeError> {
+        let stream = match mem::replace(&mut self.state, ClientState::Closed) {
+            ClientState::Entry(handshake) => handshake.into_stream()?,
+            _ => unreachable!("sessions are only started from the entry state"),
+        };
+        let read_stream = stream.try_clone().map_err(|e| HandshakeError::Network(NetworkError::Io(e)))?;
+        let writer: SharedWriter<S> = Arc::new(Mutex::new(stream));
+        let (events_tx, events) = channel();
+        spawn_event_reader(read_stream, writer.clone(), events_tx);
+
+        self.resume_token = logged_in.resume_token.clone();
+        self.state = ClientState::Session { writer, events: Some(events), forwarded: None, closed: Arc::new(AtomicBool::new(false)) };
+        Ok(logged_in)
+    }
+
+    fn writer(&self) -> Result<std::sync::MutexGuard<'_, S>, NetworkError> {
+        match &self.state {
+            ClientState::Session { writer, .. } => writer.lock().map_err(|_| NetworkError::Io(io::Error::other("the connection is poisoned"))),
+            _ => Err(NetworkError::Io(io::Error::new(io::ErrorKind::NotConnected, "not logged in"))),
+        }
+    }
+
+    /// Sends a message with its data to the server
+    pub fn send<M: Into<String>>(&self, message: M, data: M) -> Result<(), NetworkError> {
+        write_event_message(&mut *self.writer()?, message, data)
+    }
+
+    fn events(&self) -> Result<&Receiver<EventResult>, NetworkError> {
+        match &self.state {
+            ClientState::Session { events: Some(events), .. } => Ok(events),
+            _ => Err(NetworkError::Closed),
+        }
+    }
+
+    /// Waits for the next event from the server.
+    /// Keepalives are answered on their own and never returned.
+    pub fn next_event(&self) -> Result<ServerEvent, NetworkError> {
+        loop {
+            match self.events()?.recv() {
+                Ok(Ok(ServerEvent::Keepalive(_))) => continue,
+                Ok(event) => return event,
+                Err(_) => return Err(NetworkError::Closed),
+            }
+        }
+    }
+
+    /// Like `next_event`, but gives up after `timeout` and returns None
+    pub fn next_event_timeout(&self, timeout: Duration) -> Option<Result<ServerEvent, NetworkError>> {
+        let events = match self.events() {
+            Ok(events) => events,
+            Err(e) => return Some(Err(e)),
+        };
+        loop {
+            match events.recv_timeout(timeout) {
+                Ok(Ok(ServerEvent::Keepalive(_))) => continue,
+                Ok(event) => return Some(event),
+                Err(RecvTimeoutError::Timeout) => return None,
+                Err(RecvTimeoutError::Disconnected) => return Some(Err(NetworkError::Closed)),
+            }
+        }
+    }
+
+    /// Returns the next event if one has already arrived
+    pub fn try_next_event(&self) -> Option<Result<ServerEvent, NetworkError>> {
+        let events = match self.events() {
+            Ok(events) => events,
+            Err(e) => return Some(Err(e)),
+        };
+        loop {
+            match events.try_recv() {
+                Ok(Ok(ServerEvent::Keepalive(_))) => continue,
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return Some(Err(NetworkError::Closed)),
+            }
+        }
+    }
+
+    /// Forwards the session's events to `sink` from now on, for programs that wait on other input as well.
+    /// `next_event` stops returning events once they are forwarded.
+    /// Forwarding stops when the client disconnects, so closing the connection on purpose doesn't
+    /// show up in `sink` as a lost connection, which may be shared with the next client.
+    pub fn forward_events<T>(&mut self, sink: Sender<T>)
+        where T: From<EventResult> + Send + 'static {
+        if let ClientState::Session { events: events @ Some(_), forwarded, closed, .. } = &mut self.state {
+            let events = events.take().unwrap();
+            let closed = closed.clone();
+            let (done, done_rx) = channel::<()>();
+            *forwarded = Some(done_rx);
+            thread::spawn(move || {
+                // dropped when the thread ends, which is all `logout` waits for
+                let _done = done;
+                for event in events.iter() {
+                    if closed.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if matches!(event, Ok(ServerEvent::Keepalive(_))) {
+                        continue;
+                    }
+                    if sink.send(T::from(event)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Logs out: tells the server why the client is leaving, waits up to `timeout` for it to
+    /// acknowledge with its own disconnect (or by closing the connection), then closes the connection.
+    /// Returns whether the server acknowledged in time. Events that arrive meanwhile are dropped,
+    /// unless they are forwarded, in which case the acknowledgement is forwarded too.
+    pub fn logout(&mut self, reason: &str, timeout: Duration) -> Result<bool, NetworkError> {
+        let sent = self.writer().and_then(|mut writer| write_event_error(&mut *writer, reason, true));
+        if let Err(e) = sent {
+            self.disconnect();
+            return Err(e);
+        }
+
+        let deadline = Instant::now() + timeout;
+        let acknowledged = match &self.state {
+            ClientState::Session { events: Some(events), .. } => loop {
+                match events.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(Ok(ServerEvent::Disconnected(_))) | Ok(Err(_)) | Err(RecvTimeoutError::Disconnected) => break true,
+                    Ok(Ok(_)) => continue,
+                    Err(RecvTimeoutError::Timeout) => break false,
+                }
+            },
+            ClientState::Session { forwarded: Some(forwarded), .. } => {
+                matches!(forwarded.recv_timeout(timeout), Err(RecvTimeoutError::Disconnected))
+            }
+            _ => false,
+        };
+        self.disconnect();
+        Ok(acknowledged)
+    }
+
+    /// Closes the connection without telling the server, see `logout`
+    pub fn disconnect(&mut self) {
+        if let ClientState::Session { writer, closed, .. } = &self.state {
+            // set before the shutdown, so the reader's failure isn't forwarded
+            closed.store(true, Ordering::SeqCst);
+            if let Ok(writer) = writer.lock() {
+                let _ = writer.shutdown();
+            }
+        }
+        self.state = ClientState::Closed;
+    }
+}
+
+impl<S: Transport> Drop for Client<S> {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
@@ -1,13 +1,104 @@
+use std::fmt;
 use hashbrown::HashMap;
-use std::env::args;
 
+pub mod grammar;
+pub mod policy;
+pub mod signature;
+
+use policy::Policy;
+use signature::{Args, Signature};
+
+/// Why a command could not be run
+#[derive(Clone, PartialEq, Debug)]
 pub enum CommandFailReason {
-    InvalidCommand,
+    /// the input could not be split into a command, see `grammar`
+    Malformed(String),
     NoCommandGiven,
+    /// no command has this name, the closest names are suggested instead
+    InvalidCommand {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// the arguments did not fit the command's signature
+    InvalidArguments {
+        command: String,
+        reason: String,
+        usage: String,
+    },
+    /// a `Policy` kept the command from running
+    Blocked {
+        command: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CommandFailReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandFailReason::Malformed(reason) => write!(f, "{}", reason),
+            CommandFailReason::NoCommandGiven => write!(f, "Can not process an empty command!"),
+            CommandFailReason::InvalidCommand { name, suggestions } => {
+                write!(f, "The command '{}' does not exist!", name)?;
+                if !suggestions.is_empty() {
+                    write!(f, " Did you mean {}?", suggestions.iter().map(|s| format!("'{}'", s)).collect::<Vec<String>>().join(" or "))?;
+                }
+                Ok(())
+            }
+            CommandFailReason::InvalidArguments { reason, usage, .. } => write!(f, "{}\nUsage: {}", reason, usage),
+            CommandFailReason::Blocked { command, reason } => write!(f, "The command '{}' was blocked, {}", command, reason),
+        }
+    }
+}
+
+/// What a command is for, for listing and help texts
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CommandInfo {
+    pub name: String,
+    pub description: String,
+    /// how the command is typed, made from its signature for typed commands
+    pub usage: String,
+    /// groups commands in listings, empty for commands without one
+    pub category: String,
+}
+
+impl CommandInfo {
+    pub fn describe<S: Into<String>>(&mut self, description: S) -> &mut Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn in_category<S: Into<String>>(&mut self, category: S) -> &mut Self {
+        self.category = category.into();
+        self
+    }
+
+    /// Sets the usage line, for commands that take raw arguments
+    pub fn with_usage<S: Into<String>>(&mut self, usage: S) -> &mut Self {
+        self.usage = usage.into();
+        self
+    }
+}
+
+enum Handler<T> {
+    /// gets the arguments as they were sent
+    Raw(Box<dyn Fn(Vec<String>) -> T>),
+    /// gets the arguments checked against the signature
+    Typed(Signature, Box<dyn Fn(Args) -> T>),
+}
+
+struct Command<T> {
+    info: CommandInfo,
+    handler: Handler<T>,
 }
 
 pub struct CommandMuncher<T> {
-    commands: HashMap<String, Box<dyn Fn(Vec<String>) -> T>>,
+    commands: HashMap<String, Command<T>>,
+}
+
+impl<T> Default for CommandMuncher<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> CommandMuncher<T> {
@@ -17,30 +108,200 @@ impl<T> CommandMuncher<T> {
         }
     }
 
-    pub fn register<F: 'static + Fn(Vec<String>) -> T, S: Into<String>>(&mut self, command: S, handler: F) {
-        self.commands.insert(command.into(), Box::new(handler));
+    fn insert(&mut self, name: String, usage: String, handler: Handler<T>) -> &mut CommandInfo {
+        let info = CommandInfo { name: name.clone(), usage, ..CommandInfo::default() };
+        self.commands.insert(name.clone(), Command { info, handler });
+        &mut self.commands.get_mut(&name).unwrap().info
+    }
+
+    /// Registers a handler that gets the arguments as they were sent.
+    /// Returns the command's info, to describe it.
+    pub fn register<F: 'static + Fn(Vec<String>) -> T, S: Into<String>>(&mut self, command: S, handler: F) -> &mut CommandInfo {
+        let command = command.into();
+        self.insert(command.clone(), command, Handler::Raw(Box::new(handler)))
+    }
+
+    /// Registers a handler whose arguments are checked against `signature` and converted before it runs.
+    /// Arguments that don't fit fail the command with the usage text, without calling the handler.
+    /// Returns the command's info, to describe it.
+    pub fn register_typed<F: 'static + Fn(Args) -> T, S: Into<String>>(&mut self, command: S, signature: Signature, handler: F) -> &mut CommandInfo {
+        let command = command.into();
+        let usage = signature.usage(command.as_str());
+        self.insert(command, usage, Handler::Typed(signature, Box::new(handler)))
+    }
+
+    /// The names of the registered commands, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.commands.keys().map(|name| name.as_str()).collect::<Vec<&str>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// The names of the registered commands starting with `partial`, sorted
+    pub fn complete(&self, partial: &str) -> Vec<&str> {
+        self.names().into_iter().filter(|name| name.starts_with(partial)).collect()
+    }
+
+    /// The registered commands, sorted by category and then by name
+    pub fn list(&self) -> Vec<&CommandInfo> {
+        let mut infos = self.commands.values().map(|c| &c.info).collect::<Vec<&CommandInfo>>();
+        infos.sort_unstable_by(|a, b| (a.category.as_str(), a.name.as_str()).cmp(&(b.category.as_str(), b.name.as_str())));
+        infos
+    }
+
+    pub fn info(&self, command: &str) -> Option<&CommandInfo> {
+        self.commands.get(command).map(|c| &c.info)
+    }
+
+    /// The help text of a command: its usage, and its description under it
+    pub fn help(&self, command: &str) -> Result<String, CommandFailReason> {
+        let info = self.info(command).ok_or_else(|| self.unknown(command))?;
+        if info.description.is_empty() {
+            Ok(info.usage.clone())
+        } else {
+            Ok(format!("{}\n  {}", info.usage, info.description))
+        }
+    }
+
+    /// The signature of a command, if it was registered with one
+    pub fn signature(&self, command: &str) -> Option<&Signature> {
+        match &self.commands.get(command)?.handler {
+            Handler::Typed(signature, _) => Some(signature),
+            Handler::Raw(_) => None,
+        }
+    }
+
+    /// The usage line of a command
+    pub fn usage(&self, command: &str) -> Option<String> {
+        self.info(command).map(|info| info.usage.clone())
     }
 
     pub fn unregister<S: Into<String>>(&mut self, command: S) {
         self.commands.remove(&(command.into()));
     }
 
-    pub fn munch<S: Into<String>>(&mut self, input: S) -> Result<T, String> {
-        let full = input.into(); // convert the input to a string
-        if full.is_empty() { // ensure there is an actual command to nom
-            return Err(format!("Can not process an empty command!"));
+    /// The failure for a command that doesn't exist, with the names closest to it
+    fn unknown(&self, command: &str) -> CommandFailReason {
+        // more typos are allowed in longer names
+        let allowed = command.chars().count().div_ceil(3).max(1);
+        let mut close = self.commands.keys()
+            .map(|name| (edit_distance(command, name), name))
+            .filter(|(distance, _)| *distance <= allowed)
+            .collect::<Vec<(usize, &String)>>();
+        close.sort_unstable();
+        CommandFailReason::InvalidCommand {
+            name: command.to_string(),
+            suggestions: close.into_iter().take(3).map(|(_, name)| name.clone()).collect(),
+        }
+    }
+
+    /// Parses `input` as a single command, see `grammar` for the quoting rules, and runs it
+    pub fn munch<S: Into<String>>(&mut self, input: S) -> Result<T, CommandFailReason> {
+        let args = grammar::parse_command(input.into().as_str()).map_err(CommandFailReason::Malformed)?;
+        self.munch_args(args)
+    }
+
+    /// Runs an already split command, the first element being the command name
+    pub fn munch_args(&mut self, args: Vec<String>) -> Result<T, CommandFailReason> {
+        self.munch_args_with(args, &Policy::default())
+    }
+
+    /// Runs an already split command like `munch_args`, unless `policy` blocks it.
+    /// Commands registered without a signature can't have their arguments bounded.
+    pub fn munch_args_with(&mut self, mut args: Vec<String>, policy: &Policy) -> Result<T, CommandFailReason> {
+        if args.is_empty() { // ensure there is an actual command to nom
+            return Err(CommandFailReason::NoCommandGiven);
         }
-        let split = full.split(" "); // split the command by whitespace
-        let args_strs = split.collect::<Vec<&str>>(); // convert to a vector
-        let mut args = args_strs.iter().map(|s| s.to_string()).collect::<Vec<String>>();
         let command = args.remove(0); // get the command and remove it from args
 
-        let closure = self.commands.get(&command); // attempt to get the closure
-        if closure.is_none() { // if there was no closure found, fail
-            return Err(format!("The command '{}' does not exist!", command));
+        let found = match self.commands.get(&command) {
+            Some(found) => found,
+            None => return Err(self.unknown(command.as_str())),
+        };
+
+        if let Err(reason) = policy.permits(command.as_str()) {
+            return Err(CommandFailReason::Blocked { command, reason });
+        }
+
+        match &found.handler {
+            Handler::Raw(handler) => Ok(handler(args)),
+            Handler::Typed(signature, handler) => {
+                let args = signature.parse(args)
+                    .map_err(|reason| CommandFailReason::InvalidArguments { command: command.clone(), reason, usage: found.info.usage.clone() })?;
+                match policy.check_bounds(command.as_str(), &args) {
+                    Ok(()) => Ok(handler(args)),
+                    Err(reason) => Err(CommandFailReason::Blocked { command, reason }),
+                }
+            }
         }
+    }
+}
 
-        let cl = closure.unwrap(); // get the closure to run
-        Ok(cl(args)) // return the result of the closure
+/// How many single character insertions, deletions and substitutions turn `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
     }
-}
\ No newline at end of file
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signature::ArgType;
+
+    fn muncher() -> CommandMuncher<String> {
+        let mut muncher = CommandMuncher::new();
+        muncher.register_typed("quit", Signature::new().variadic("reason", ArgType::Text), |args| args.rest::<String>("reason").join(" "))
+            .describe("Leaves the game")
+            .in_category("Session");
+        muncher.register_typed("query", Signature::new().arg("name", ArgType::Text), |args| args.get("name"));
+        muncher.register("echo", |args| args.join(" "))
+            .with_usage("echo <anything>")
+            .in_category("Debug");
+        muncher
+    }
+
+    #[test]
+    fn commands_describe_themselves() {
+        let muncher = muncher();
+        let listed = muncher.list().iter().map(|info| info.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(listed, vec!["query", "echo", "quit"]);
+        assert_eq!(muncher.help("quit").unwrap(), "quit [reason: text...]\n  Leaves the game");
+        assert_eq!(muncher.help("echo").unwrap(), "echo <anything>");
+    }
+
+    #[test]
+    fn failures_are_structured() {
+        let mut muncher = muncher();
+        assert_eq!(muncher.munch("echo hi there").unwrap(), "hi there");
+        assert_eq!(muncher.munch("").unwrap_err(), CommandFailReason::NoCommandGiven);
+        assert!(matches!(muncher.munch("echo \"open").unwrap_err(), CommandFailReason::Malformed(_)));
+
+        let err = muncher.munch("query").unwrap_err();
+        assert_eq!(err.to_string(), "Missing argument <name: text>\nUsage: query <name: text>");
+        assert!(matches!(err, CommandFailReason::InvalidArguments { ref command, .. } if command == "query"));
+
+        let err = muncher.munch("qiut now").unwrap_err();
+        assert_eq!(err, CommandFailReason::InvalidCommand { name: "qiut".to_string(), suggestions: vec!["quit".to_string()] });
+        assert_eq!(err.to_string(), "The command 'qiut' does not exist! Did you mean 'quit'?");
+        assert_eq!(muncher.help("quet").unwrap_err().to_string(), "The command 'quet' does not exist! Did you mean 'quit' or 'query'?");
+        assert_eq!(muncher.munch("dance").unwrap_err().to_string(), "The command 'dance' does not exist!");
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("quit", "quit"), 0);
+        assert_eq!(edit_distance("qiut", "quit"), 2);
+    }
+}
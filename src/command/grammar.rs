@@ -0,0 +1,173 @@
+//! The grammar of the command stream servers send in `Message.data`.
+//!
+//! - commands are separated by `;`, empty commands are skipped
+//! - the words of a command are separated by spaces or tabs, the first word is the command name
+//! - `"..."` quotes a word, keeping spaces and `;` in it. `""` is an empty word
+//! - a backslash escapes the next character, inside quotes or not:
+//!   `\\` `\"` `\;` `\ ` are the character itself, `\n` `\t` `\r` are newline, tab and carriage return
+//!
+//! For example `println "  two spaces; then \"quotes\""; clear` runs
+//! `println` with the single argument `  two spaces; then "quotes"`, then `clear`.
+
+/// Splits a command stream into commands, each a list of words starting with the command name
+pub fn parse(data: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    // the word being read, None between words
+    let mut word: Option<String> = None;
+    let mut quoted_at = None;
+
+    let mut chars = data.chars().enumerate();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some((_, e)) => unescape(e).ok_or_else(|| format!("Unknown escape '\\{}' at position {}", e, pos))?,
+                    None => return Err(format!("Unfinished escape at position {}", pos)),
+                };
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            '"' if quoted_at.is_none() => {
+                quoted_at = Some(pos);
+                word.get_or_insert_with(String::new);
+            }
+            '"' => quoted_at = None,
+            _ if quoted_at.is_some() => word.get_or_insert_with(String::new).push(c),
+            ' ' | '\t' => words.extend(word.take()),
+            ';' => {
+                words.extend(word.take());
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(pos) = quoted_at {
+        return Err(format!("Unclosed quote at position {}", pos));
+    }
+    words.extend(word.take());
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    Ok(commands)
+}
+
+/// Splits a single command into its words
+pub fn parse_command(command: &str) -> Result<Vec<String>, String> {
+    let mut commands = parse(command)?;
+    match commands.len() {
+        0 => Ok(Vec::new()),
+        1 => Ok(commands.remove(0)),
+        _ => Err("Expected a single command, but found several separated by ';'".to_string()),
+    }
+}
+
+fn unescape(c: char) -> Option<char> {
+    match c {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '\\' | '"' | ';' | ' ' => Some(c),
+        _ => None,
+    }
+}
+
+/// Quotes a word if it needs it, so it parses back to exactly the same text
+pub fn quote(word: &str) -> String {
+    let plain = !word.is_empty() && word.chars().all(|c| !matches!(c, ' ' | '\t' | '\n' | '\r' | ';' | '"' | '\\'));
+    if plain {
+        return word.to_string();
+    }
+
+    let mut quoted = String::with_capacity(word.len() + 2);
+    quoted.push('"');
+    for c in word.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Builds a command stream that parses back into `commands`
+pub fn encode<S: AsRef<str>>(commands: &[Vec<S>]) -> String {
+    commands.iter()
+        .map(|words| words.iter().map(|w| quote(w.as_ref())).collect::<Vec<String>>().join(" "))
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_commands_and_words() {
+        assert_eq!(parse("clear;println hello  world;;  cursor 1 2 ;").unwrap(), vec![
+            words(&["clear"]),
+            words(&["println", "hello", "world"]),
+            words(&["cursor", "1", "2"]),
+        ]);
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse(" ; ;").unwrap().is_empty());
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(parse(r#"println "  two spaces; then \"quotes\""; clear"#).unwrap(), vec![
+            words(&["println", "  two spaces; then \"quotes\""]),
+            words(&["clear"]),
+        ]);
+        assert_eq!(parse(r"print a\;b\ c\\d\n").unwrap(), vec![words(&["print", "a;b c\\d\n"])]);
+        assert_eq!(parse(r#"print "" x"y z"w"#).unwrap(), vec![words(&["print", "", "xy zw"])]);
+    }
+
+    #[test]
+    fn rejects_broken_input() {
+        assert!(parse(r#"println "unclosed"#).is_err());
+        assert!(parse(r"println trailing\").is_err());
+        assert!(parse(r"println \q").is_err());
+        assert!(parse_command("clear; clear").is_err());
+    }
+
+    #[test]
+    fn arbitrary_text_round_trips() {
+        let samples = [
+            "", " ", "  leading", "trailing  ", "semi;colon", ";", "\"", "\\", "\\\"", "new\nline", "tab\there",
+            "carriage\rreturn", "!;!", "unicode ✓ ünïcödé", "\"quoted\"", "back\\slash;\"mixed\" up ",
+        ];
+        for sample in samples.iter() {
+            let commands = vec![vec!["println".to_string(), sample.to_string()]];
+            assert_eq!(parse(encode(&commands).as_str()).unwrap(), commands, "sample {:?}", sample);
+        }
+
+        // every combination of the special characters, a few at a time
+        let alphabet = [' ', '\t', '\n', ';', '"', '\\', 'a', '!'];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let mut command = vec!["print".to_string()];
+            for _ in 0..3 {
+                // xorshift, so the test stays deterministic without extra dependencies
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let len = (state % 6) as usize;
+                command.push((0..len).map(|i| alphabet[((state >> (8 + i * 3)) % alphabet.len() as u64) as usize]).collect());
+            }
+            let commands = vec![command.clone(), command];
+            assert_eq!(parse(encode(&commands).as_str()).unwrap(), commands);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use crate::command::signature::Args;
+
+/// Limits on a numeric argument, either end can be left open
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Bounds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+}
+
+/// What the other side may make a `CommandMuncher` do. The default allows everything.
+///
+/// ```
+/// use mini_mmo_client::command::policy::Policy;
+/// let policy: Policy = toml::from_str(r#"
+///     deny = ["size"]
+///     max_per_message = 20
+///     bounds = { "cursor.x" = { max = 200 } }
+/// "#).unwrap();
+/// assert!(policy.permits("cursor").is_ok());
+/// assert!(policy.permits("size").is_err());
+/// ```
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Policy {
+    /// only these commands may run, or any that isn't denied if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// limits for numeric arguments, keyed by `command.argument`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bounds: BTreeMap<String, Bounds>,
+    /// how many commands a single message may run, the rest are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_message: Option<usize>,
+}
+
+impl Policy {
+    /// Whether `command` may run at all
+    pub fn permits(&self, command: &str) -> Result<(), String> {
+        if self.deny.iter().any(|denied| denied == command) {
+            return Err("it is denied for this server".to_string());
+        }
+        match &self.allow {
+            Some(allowed) if !allowed.iter().any(|allowed| allowed == command) => Err("it is not allowed for this server".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the numeric arguments of `command` against their bounds
+    pub fn check_bounds(&self, command: &str, args: &Args) -> Result<(), String> {
+        for (name, value) in args.numbers() {
+            let bounds = match self.bounds.get(format!("{}.{}", command, name).as_str()) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            // NaN compares false against both ends, so it would slip through any bounds
+            if !value.is_finite() {
+                return Err(format!("{} is {}, which isn't a finite number", name, value));
+            }
+            if let Some(min) = bounds.min.filter(|&min| value < min as f64) {
+                return Err(format!("{} is {}, below the minimum of {}", name, value, min));
+            }
+            if let Some(max) = bounds.max.filter(|&max| value > max as f64) {
+                return Err(format!("{} is {}, above the maximum of {}", name, value, max));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits off the commands of a message that are over the limit, returning the ones that may run and the dropped ones
+    pub fn limit(&self, mut commands: Vec<Vec<String>>) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+        let max = self.max_per_message.unwrap_or(usize::MAX).min(commands.len());
+        let dropped = commands.split_off(max);
+        (commands, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{CommandFailReason, CommandMuncher};
+    use crate::command::signature::{ArgType, Signature};
+
+    fn commands(names: &[&str]) -> Vec<Vec<String>> {
+        names.iter().map(|name| vec![name.to_string()]).collect()
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let policy = Policy {
+            allow: Some(vec!["cursor".to_string(), "size".to_string()]),
+            deny: vec!["size".to_string()],
+            ..Policy::default()
+        };
+        assert!(policy.permits("cursor").is_ok());
+        assert_eq!(policy.permits("size").unwrap_err(), "it is denied for this server");
+        assert_eq!(policy.permits("clear").unwrap_err(), "it is not allowed for this server");
+        assert!(Policy::default().permits("clear").is_ok());
+    }
+
+    #[test]
+    fn bounds_can_be_open_ended() {
+        let mut policy = Policy::default();
+        policy.bounds.insert("move.x".to_string(), Bounds { min: Some(-10), max: None });
+        policy.bounds.insert("move.y".to_string(), Bounds { min: None, max: Some(10) });
+        let signature = Signature::new().arg("x", ArgType::F64).arg("y", ArgType::F64);
+        let check = |x: &str, y: &str| policy.check_bounds("move", &signature.parse(vec![x.to_string(), y.to_string()]).unwrap());
+
+        assert!(check("1e9", "-1e9").is_ok());
+        assert!(check("-10", "10").is_ok());
+        assert_eq!(check("-11", "0").unwrap_err(), "x is -11, below the minimum of -10");
+        assert_eq!(check("0", "10.5").unwrap_err(), "y is 10.5, above the maximum of 10");
+        assert_eq!(check("NaN", "0").unwrap_err(), "x is NaN, which isn't a finite number");
+        assert_eq!(check("0", "-inf").unwrap_err(), "y is -inf, which isn't a finite number");
+        // arguments without bounds aren't checked
+        assert!(policy.check_bounds("jump", &signature.parse(vec!["NaN".to_string(), "0".to_string()]).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn commands_over_the_limit_are_dropped() {
+        let policy = Policy { max_per_message: Some(2), ..Policy::default() };
+        assert_eq!(policy.limit(commands(&["a", "b", "c"])), (commands(&["a", "b"]), commands(&["c"])));
+        assert_eq!(policy.limit(commands(&["a"])), (commands(&["a"]), commands(&[])));
+        assert_eq!(Policy::default().limit(commands(&["a", "b", "c"])).1, commands(&[]));
+    }
+
+    #[test]
+    fn munchers_block_what_the_policy_forbids() {
+        let mut muncher = CommandMuncher::new();
+        muncher.register_typed("size", Signature::new().arg("width", ArgType::U16).arg("height", ArgType::U16), |args| args.get::<u16>("width"));
+        muncher.register("echo", |args| args.len() as u16);
+        let mut policy = Policy { allow: Some(vec!["size".to_string()]), ..Policy::default() };
+        policy.bounds.insert("size.width".to_string(), Bounds { min: Some(40), max: Some(300) });
+
+        assert_eq!(muncher.munch_args_with(vec!["size".into(), "80".into(), "5".into()], &policy).unwrap(), 80);
+        let err = muncher.munch_args_with(vec!["size".into(), "10".into(), "5".into()], &policy).unwrap_err();
+        assert_eq!(err.to_string(), "The command 'size' was blocked, width is 10, below the minimum of 40");
+        assert!(matches!(muncher.munch_args_with(vec!["echo".into()], &policy), Err(CommandFailReason::Blocked { .. })));
+        // unknown commands are still reported as unknown
+        assert!(matches!(muncher.munch_args_with(vec!["nope".into()], &policy), Err(CommandFailReason::InvalidCommand { .. })));
+    }
+}
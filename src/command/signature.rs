@@ -0,0 +1,350 @@
+use std::fmt;
+use std::str::FromStr;
+use hashbrown::HashMap;
+
+/// The type an argument is parsed as
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArgType {
+    Text,
+    Bool,
+    U8,
+    U16,
+    U32,
+    I64,
+    F64,
+}
+
+impl fmt::Display for ArgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArgType::Text => "text",
+            ArgType::Bool => "bool",
+            ArgType::U8 => "u8",
+            ArgType::U16 => "u16",
+            ArgType::U32 => "u32",
+            ArgType::I64 => "i64",
+            ArgType::F64 => "f64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ArgType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "text" => ArgType::Text,
+            "bool" => ArgType::Bool,
+            "u8" => ArgType::U8,
+            "u16" => ArgType::U16,
+            "u32" => ArgType::U32,
+            "i64" => ArgType::I64,
+            "f64" => ArgType::F64,
+            _ => return Err(format!("Unknown argument type '{}'", name)),
+        })
+    }
+}
+
+impl ArgType {
+    fn parse(self, raw: &str) -> Option<Value> {
+        Some(match self {
+            ArgType::Text => Value::Text(raw.to_string()),
+            ArgType::Bool => match raw {
+                "true" | "yes" | "on" | "1" => Value::Bool(true),
+                "false" | "no" | "off" | "0" => Value::Bool(false),
+                _ => return None,
+            },
+            ArgType::U8 => Value::U8(raw.parse().ok()?),
+            ArgType::U16 => Value::U16(raw.parse().ok()?),
+            ArgType::U32 => Value::U32(raw.parse().ok()?),
+            ArgType::I64 => Value::I64(raw.parse().ok()?),
+            ArgType::F64 => Value::F64(raw.parse().ok()?),
+        })
+    }
+}
+
+/// A parsed argument
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Text(String),
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    I64(i64),
+    F64(f64),
+}
+
+impl Value {
+    /// The value as a number, None for text and booleans
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match self {
+            Value::U8(v) => f64::from(*v),
+            Value::U16(v) => f64::from(*v),
+            Value::U32(v) => f64::from(*v),
+            Value::I64(v) => *v as f64,
+            Value::F64(v) => *v,
+            Value::Text(_) | Value::Bool(_) => return None,
+        })
+    }
+}
+
+/// Types a handler can take its arguments as
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+macro_rules! from_value {
+    ($($variant:ident => $ty:ty),*) => {
+        $(impl FromValue for $ty {
+            fn from_value(value: &Value) -> Option<Self> {
+                match value {
+                    Value::$variant(v) => Some(v.clone()),
+                    _ => None,
+                }
+            }
+        })*
+    };
+}
+
+from_value!(Text => String, Bool => bool, U8 => u8, U16 => u16, U32 => u32, I64 => i64, F64 => f64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Arity {
+    Required,
+    Optional,
+    Variadic,
+}
+
+#[derive(Clone, Debug)]
+struct Param {
+    name: String,
+    kind: ArgType,
+    arity: Arity,
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.arity {
+            Arity::Required => write!(f, "<{}: {}>", self.name, self.kind),
+            Arity::Optional => write!(f, "[{}: {}]", self.name, self.kind),
+            Arity::Variadic => write!(f, "[{}: {}...]", self.name, self.kind),
+        }
+    }
+}
+
+/// The arguments a command takes, in order: required ones, then optional ones, then at most one variadic.
+///
+/// ```
+/// use mini_mmo_client::command::signature::{ArgType, Signature};
+/// let signature = Signature::new().arg("x", ArgType::U16).arg("y", ArgType::U16);
+/// assert_eq!(signature.usage("cursor"), "cursor <x: u16> <y: u16>");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Signature {
+    params: Vec<Param>,
+}
+
+impl Signature {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, name: &str, kind: ArgType, arity: Arity) -> Self {
+        if let Some(last) = self.params.last() {
+            assert!(last.arity != Arity::Variadic, "the variadic argument has to be the last one");
+            assert!(arity != Arity::Required || last.arity == Arity::Required, "required arguments have to come before optional ones");
+        }
+        self.params.push(Param { name: name.to_string(), kind, arity });
+        self
+    }
+
+    /// Adds a required argument
+    pub fn arg(self, name: &str, kind: ArgType) -> Self {
+        self.push(name, kind, Arity::Required)
+    }
+
+    /// Adds an argument that can be left out
+    pub fn optional(self, name: &str, kind: ArgType) -> Self {
+        self.push(name, kind, Arity::Optional)
+    }
+
+    /// Takes all remaining arguments, there may be none
+    pub fn variadic(self, name: &str, kind: ArgType) -> Self {
+        self.push(name, kind, Arity::Variadic)
+    }
+
+    /// The usage line for `command`, e.g. `cursor <x: u16> <y: u16>`
+    pub fn usage(&self, command: &str) -> String {
+        let mut usage = command.to_string();
+        for param in self.params.iter() {
+            usage.push_str(format!(" {}", param).as_str());
+        }
+        usage
+    }
+
+    /// Reads a usage line back into the command name and its signature, the reverse of `usage`
+    pub fn from_usage(usage: &str) -> Result<(String, Signature), String> {
+        let usage = usage.trim();
+        let (name, mut rest) = match usage.find(char::is_whitespace) {
+            Some(end) => (&usage[..end], usage[end..].trim_start()),
+            None => (usage, ""),
+        };
+        if name.is_empty() {
+            return Err("The usage line is empty".to_string());
+        }
+
+        let mut signature = Signature::new();
+        while !rest.is_empty() {
+            let close = match rest.chars().next() {
+                Some('<') => '>',
+                Some('[') => ']',
+                _ => return Err(format!("Expected an argument in '{}' at '{}'", usage, rest)),
+            };
+            let end = rest.find(close).ok_or_else(|| format!("Unclosed argument in '{}'", usage))?;
+            let (param, kind) = rest[1..end].split_once(':')
+                .ok_or_else(|| format!("The argument {} in '{}' has no type", &rest[..=end], usage))?;
+            let (kind, variadic) = match kind.trim().strip_suffix("...") {
+                Some(kind) => (kind, true),
+                None => (kind.trim(), false),
+            };
+            let kind = kind.parse::<ArgType>().map_err(|e| format!("{} in '{}'", e, usage))?;
+            let arity = match (close, variadic) {
+                ('>', false) => Arity::Required,
+                (']', false) => Arity::Optional,
+                (']', true) => Arity::Variadic,
+                _ => return Err(format!("Only optional arguments can be variadic in '{}'", usage)),
+            };
+            // the same rules the builder asserts, but a server sending them wrong isn't a bug in the client
+            if let Some(last) = signature.params.last() {
+                if last.arity == Arity::Variadic {
+                    return Err(format!("The variadic argument has to be the last one in '{}'", usage));
+                }
+                if arity == Arity::Required && last.arity != Arity::Required {
+                    return Err(format!("Required arguments have to come before optional ones in '{}'", usage));
+                }
+            }
+            signature.params.push(Param { name: param.trim().to_string(), kind, arity });
+            rest = rest[end + 1..].trim_start();
+        }
+        Ok((name.to_string(), signature))
+    }
+
+    /// The usage of the arguments after the first `given` ones, for hinting what to type next
+    pub fn hint(&self, given: usize) -> String {
+        self.params.iter().skip(given).map(|param| param.to_string()).collect::<Vec<String>>().join(" ")
+    }
+
+    /// Checks the raw arguments against the signature and converts them
+    pub fn parse(&self, raw: Vec<String>) -> Result<Args, String> {
+        let mut values: HashMap<String, Vec<Value>> = HashMap::new();
+        let mut raw = raw.into_iter();
+
+        for param in self.params.iter() {
+            let taken: Vec<String> = match param.arity {
+                Arity::Required => vec![raw.next().ok_or_else(|| format!("Missing argument {}", param))?],
+                Arity::Optional => raw.next().into_iter().collect(),
+                Arity::Variadic => raw.by_ref().collect(),
+            };
+            let parsed = taken.iter()
+                .map(|arg| param.kind.parse(arg).ok_or_else(|| format!("Invalid argument {}: '{}' is not a {}", param, arg, param.kind)))
+                .collect::<Result<Vec<Value>, String>>()?;
+            values.insert(param.name.clone(), parsed);
+        }
+
+        let extra = raw.count();
+        if extra > 0 {
+            return Err(format!("Too many arguments, {} more than expected", extra));
+        }
+        Ok(Args { values })
+    }
+}
+
+/// Arguments that passed a signature, looked up by name.
+/// Asking for an argument the signature doesn't declare, or as the wrong type, is a bug in the handler and panics.
+#[derive(Debug)]
+pub struct Args {
+    values: HashMap<String, Vec<Value>>,
+}
+
+impl Args {
+    fn values<V: FromValue>(&self, name: &str) -> Vec<V> {
+        let values = self.values.get(name).unwrap_or_else(|| panic!("the signature has no argument '{}'", name));
+        values.iter()
+            .map(|v| V::from_value(v).unwrap_or_else(|| panic!("argument '{}' was asked for as the wrong type", name)))
+            .collect()
+    }
+
+    /// A required argument
+    pub fn get<V: FromValue>(&self, name: &str) -> V {
+        self.opt(name).unwrap_or_else(|| panic!("argument '{}' is optional", name))
+    }
+
+    /// An optional argument, None if it was left out
+    pub fn opt<V: FromValue>(&self, name: &str) -> Option<V> {
+        self.values(name).into_iter().next()
+    }
+
+    /// All values of the variadic argument
+    pub fn rest<V: FromValue>(&self, name: &str) -> Vec<V> {
+        self.values(name)
+    }
+
+    /// Every numeric value that was given, with the name of its argument
+    pub fn numbers(&self) -> Vec<(&str, f64)> {
+        self.values.iter()
+            .flat_map(|(name, values)| values.iter().filter_map(move |value| value.as_f64().map(|n| (name.as_str(), n))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_typed_arguments() {
+        let signature = Signature::new()
+            .arg("x", ArgType::U16)
+            .optional("bold", ArgType::Bool)
+            .variadic("words", ArgType::Text);
+
+        let args = signature.parse(raw(&["12", "yes", "hello", "world"])).unwrap();
+        assert_eq!(args.get::<u16>("x"), 12);
+        assert_eq!(args.opt::<bool>("bold"), Some(true));
+        assert_eq!(args.rest::<String>("words"), raw(&["hello", "world"]));
+
+        let args = signature.parse(raw(&["12"])).unwrap();
+        assert_eq!(args.opt::<bool>("bold"), None);
+        assert!(args.rest::<String>("words").is_empty());
+    }
+
+    #[test]
+    fn reports_bad_arguments() {
+        let signature = Signature::new().arg("x", ArgType::U16).arg("y", ArgType::U16);
+        assert_eq!(signature.usage("cursor"), "cursor <x: u16> <y: u16>");
+        assert_eq!(signature.parse(raw(&["1"])).unwrap_err(), "Missing argument <y: u16>");
+        assert_eq!(signature.parse(raw(&["1", "-2"])).unwrap_err(), "Invalid argument <y: u16>: '-2' is not a u16");
+        assert_eq!(signature.parse(raw(&["1", "2", "3"])).unwrap_err(), "Too many arguments, 1 more than expected");
+    }
+
+    #[test]
+    fn usage_lines_are_read_back() {
+        let usage = "give <item: text> [count: u32] [to: text...]";
+        let (name, signature) = Signature::from_usage(usage).unwrap();
+        assert_eq!(name, "give");
+        assert_eq!(signature.usage(name.as_str()), usage);
+        assert_eq!(signature.hint(1), "[count: u32] [to: text...]");
+        assert_eq!(Signature::from_usage("look").unwrap().1.usage("look"), "look");
+
+        assert_eq!(Signature::from_usage("go <where>").unwrap_err(), "The argument <where> in 'go <where>' has no type");
+        assert_eq!(Signature::from_usage("go <where: place>").unwrap_err(), "Unknown argument type 'place' in 'go <where: place>'");
+        assert_eq!(Signature::from_usage("go [a: u8] <b: u8>").unwrap_err(), "Required arguments have to come before optional ones in 'go [a: u8] <b: u8>'");
+        assert!(Signature::from_usage("go <a: u8").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::env;
+use std::fs;
+use std::io::stdout;
+use std::path::PathBuf;
+use std::time::Duration;
+use crossterm::tty::IsTty;
+use serde::{Serialize, Deserialize};
+use crate::utils::data_dir;
+use crate::network::backoff::Backoff;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Client settings, stored as TOML in the client data directory.
+/// Missing keys fall back to their defaults, so older config files keep working.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub reconnect: ReconnectConfig,
+    pub commands: CommandConfig,
+    pub display: DisplayConfig,
+}
+
+/// Whether output is drawn in color
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// colors if the output is a terminal that supports them and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                let dumb = env::var("TERM").is_ok_and(|term| term == "dumb");
+                !no_color && !dumb && stdout().is_tty()
+            }
+        }
+    }
+}
+
+/// How the session is shown
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub colors: ColorMode,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            colors: ColorMode::Auto,
+        }
+    }
+}
+
+/// How typed lines are told apart from messages to the server
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CommandConfig {
+    /// lines starting with this run a local command, doubling it sends the line with one prefix.
+    /// Empty to send every line to the server
+    pub prefix: String,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "/".to_string(),
+        }
+    }
+}
+
+/// How the client reconnects after losing the connection to the server
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub enabled: bool,
+    /// delay before the first reconnect attempt
+    pub initial_delay_ms: u64,
+    /// the delay stops growing once it reaches this
+    pub max_delay_ms: u64,
+    /// how much the delay grows after each failed attempt
+    pub multiplier: f64,
+    /// 0 to keep trying forever
+    pub max_attempts: u32,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            max_attempts: 10,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    pub fn backoff(&self) -> Backoff {
+        let max_attempts = if self.max_attempts == 0 { None } else { Some(self.max_attempts) };
+        Backoff::new(
+            Duration::from_millis(self.initial_delay_ms),
+            Duration::from_millis(self.max_delay_ms),
+            self.multiplier,
+            max_attempts,
+        )
+    }
+}
+
+impl ClientConfig {
+    /// Loads the config from the client data directory, creating it with defaults if it doesn't exist
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(data_dir()?.join(CONFIG_FILE))
+    }
+
+    pub fn load_from<P: Into<PathBuf>>(path: P) -> Result<Self, String> {
+        let path = path.into();
+
+        if !path.exists() {
+            let config = Self::default();
+            let raw = toml::to_string(&config)
+                .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+            fs::write(&path, raw)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            return Ok(config);
+        }
+
+        let raw = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(raw.as_str())
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_settings_make_the_backoff() {
+        let config = ReconnectConfig { initial_delay_ms: 250, max_delay_ms: 1000, multiplier: 3.0, max_attempts: 4, ..ReconnectConfig::default() };
+        let delays = config.backoff().map(|delay| delay.as_millis()).collect::<Vec<u128>>();
+        assert_eq!(delays, vec![250, 750, 1000, 1000]);
+
+        // no limit on the attempts
+        let forever = ReconnectConfig { max_attempts: 0, ..config };
+        assert_eq!(forever.backoff().take(100).count(), 100);
+    }
+}
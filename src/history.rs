@@ -0,0 +1,199 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use crate::utils::data_dir;
+
+/// The directory in the client data directory the history files are kept in, one per server
+pub const HISTORY_DIR: &str = "history";
+
+/// How many lines are remembered per server
+pub const HISTORY_CAPACITY: usize = 1000;
+
+/// The lines the user sent in earlier sessions, oldest first, stored one per line in a text file.
+/// New lines are appended to the file, which is only rewritten without the forgotten lines
+/// once it holds twice `HISTORY_CAPACITY`, so a full history isn't rewritten for every line.
+pub struct History {
+    entries: Vec<String>,
+    /// where the history is saved, or None to keep it in memory
+    path: Option<PathBuf>,
+    /// how many lines the file holds, forgotten ones included
+    saved: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    /// A history that isn't saved anywhere
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            path: None,
+            saved: 0,
+        }
+    }
+
+    /// Loads the history of the server named `server`
+    pub fn load(server: &str) -> Result<Self, String> {
+        Self::load_from(data_dir()?.join(HISTORY_DIR).join(file_name(server)))
+    }
+
+    pub fn load_from<P: Into<PathBuf>>(path: P) -> Result<Self, String> {
+        let path = path.into();
+        let mut entries = Vec::new();
+        let mut saved = 0;
+        if path.exists() {
+            let raw = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            entries = raw.lines().filter(|line| !line.is_empty()).map(String::from).collect();
+            saved = entries.len();
+            let excess = entries.len().saturating_sub(HISTORY_CAPACITY);
+            entries.drain(..excess);
+        }
+        Ok(Self { entries, path: Some(path), saved })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry at `index`, counting from the oldest
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Remembers a line and saves the history. Blank lines and repeats of the last line are skipped.
+    pub fn push(&mut self, line: &str) -> Result<(), String> {
+        if line.trim().is_empty() || self.entries.last().map(String::as_str) == Some(line) {
+            return Ok(());
+        }
+        if self.entries.len() == HISTORY_CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push(line.to_string());
+        if self.saved >= 2 * HISTORY_CAPACITY {
+            self.compact()
+        } else {
+            self.append(line)
+        }
+    }
+
+    /// The index of the newest entry before `before` that contains `term`
+    pub fn find(&self, term: &str, before: usize) -> Option<usize> {
+        self.entries.iter().take(before).rposition(|entry| entry.contains(term))
+    }
+
+    /// The file to save to, with its directory created, or None if the history isn't saved
+    fn file(&self) -> Result<Option<&PathBuf>, String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        Ok(Some(path))
+    }
+
+    fn append(&mut self, line: &str) -> Result<(), String> {
+        let path = match self.file()? {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        OpenOptions::new().create(true).append(true).open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        self.saved += 1;
+        Ok(())
+    }
+
+    /// Rewrites the file with only the remembered lines
+    fn compact(&mut self) -> Result<(), String> {
+        let path = match self.file()? {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut raw = self.entries.join("\n");
+        raw.push('\n');
+        fs::write(path, raw)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        self.saved = self.entries.len();
+        Ok(())
+    }
+}
+
+/// The history file of a server, with anything that can't be in a file name replaced.
+/// A short hash of the exact name keeps names that read the same once replaced apart.
+fn file_name(server: &str) -> String {
+    let name = server.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>();
+    let hash = Sha256::digest(server.as_bytes()).iter()
+        .take(4)
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("{}-{}.txt", name, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_saved_per_server() {
+        let dir = std::env::temp_dir().join(format!("mini_mmo_client_history_{}", std::process::id()));
+        let path = dir.join(file_name("my server/1"));
+        assert!(path.file_name().unwrap().to_str().unwrap().starts_with("my_server_1-"));
+        assert_ne!(file_name("my server"), file_name("my_server"));
+        assert_eq!(file_name("my server"), file_name("my server"));
+
+        let mut history = History::load_from(&path).unwrap();
+        assert!(history.is_empty());
+        for line in ["look", "look", " ", "go north", "say hi"].iter() {
+            history.push(line).unwrap();
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.find("o", 3), Some(1));
+        assert_eq!(history.find("o", 1), Some(0));
+        assert_eq!(history.find("o", 0), None);
+
+        let history = History::load_from(&path).unwrap();
+        assert_eq!(history.get(0), Some("look"));
+        assert_eq!(history.get(2), Some("say hi"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn lines_are_appended_until_the_file_is_compacted() {
+        let dir = std::env::temp_dir().join(format!("mini_mmo_client_history_compact_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join(file_name("full"));
+        let lines_in_file = || fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut history = History::load_from(&path).unwrap();
+        for i in 0..2 * HISTORY_CAPACITY {
+            history.push(format!("line {}", i).as_str()).unwrap();
+        }
+        // forgotten lines stay in the file for now, but aren't loaded
+        assert_eq!(history.len(), HISTORY_CAPACITY);
+        assert_eq!(lines_in_file(), 2 * HISTORY_CAPACITY);
+        let mut history = History::load_from(&path).unwrap();
+        assert_eq!(history.len(), HISTORY_CAPACITY);
+        assert_eq!(history.get(0), Some(format!("line {}", HISTORY_CAPACITY).as_str()));
+
+        history.push("last").unwrap();
+        assert_eq!(lines_in_file(), HISTORY_CAPACITY);
+        let history = History::load_from(&path).unwrap();
+        assert_eq!(history.get(HISTORY_CAPACITY - 1), Some("last"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,16 @@
+//! The mini MMO protocol and client, shared by the terminal client and any other program
+//! that talks to the server. Most programs only need `client::Client`.
+
+pub mod utils;
+/// The wire format, generated from schema/packet.capnp by the build script
+pub mod packet_capnp {
+    include!(concat!(env!("OUT_DIR"), "/packet_capnp.rs"));
+}
+pub mod network;
+pub mod command;
+pub mod client;
+/// A stand-in for the game server, for tests and the mock_server binary
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,93 @@
+use crate::command::CommandMuncher;
+use crate::command::signature::{ArgType, Signature};
+
+/// The reason the server is given when the user quits without one
+pub const DEFAULT_QUIT_REASON: &str = "Leaving";
+
+/// How many lines `/log` shows when not told otherwise
+pub const DEFAULT_LOG_LINES: u32 = 20;
+
+/// What the session does after a local command ran
+pub enum LocalAction {
+    /// clears the output
+    Clear,
+    /// logs out, telling the server the reason
+    Quit(String),
+    /// drops the connection and resumes the session over a new one
+    Reconnect,
+    /// shows the last lines the server sent
+    ShowLog(u32),
+    /// lists the commands, or explains one
+    Help(Option<String>),
+    Servers,
+    /// searches the output, an empty term clears the search
+    Search(String),
+}
+
+/// A line the user typed, sorted by where it goes
+#[derive(PartialEq, Debug)]
+pub enum Input<'a> {
+    /// a local command, without its prefix
+    Local(&'a str),
+    /// a message for the server
+    Server(&'a str),
+}
+
+/// Sorts a line by its prefix. A doubled prefix escapes it, so `//shrug` sends `/shrug` to the server.
+pub fn classify<'a>(line: &'a str, prefix: &str) -> Input<'a> {
+    if prefix.is_empty() {
+        return Input::Server(line);
+    }
+    match line.strip_prefix(prefix) {
+        Some(rest) if rest.starts_with(prefix) => Input::Server(rest),
+        Some(rest) => Input::Local(rest),
+        None => Input::Server(line),
+    }
+}
+
+/// Registers the commands the user can run on the client itself
+pub fn register_local_commands(muncher: &mut CommandMuncher<LocalAction>) {
+    muncher.register_typed("quit", Signature::new().variadic("reason", ArgType::Text), |args| {
+        let reason = args.rest::<String>("reason").join(" ");
+        LocalAction::Quit(if reason.is_empty() { DEFAULT_QUIT_REASON.to_string() } else { reason })
+    })
+        .describe("Logs out and closes the client, telling the server why")
+        .in_category("Connection");
+    muncher.register_typed("reconnect", Signature::new(), |_args| LocalAction::Reconnect)
+        .describe("Drops the connection and resumes the session over a new one")
+        .in_category("Connection");
+    muncher.register_typed("servers", Signature::new(), |_args| LocalAction::Servers)
+        .describe("Lists the saved servers")
+        .in_category("Connection");
+    muncher.register_typed("clear", Signature::new(), |_args| LocalAction::Clear)
+        .describe("Clears the output")
+        .in_category("Output");
+    muncher.register_typed("log", Signature::new().optional("lines", ArgType::U32), |args| {
+        LocalAction::ShowLog(args.opt("lines").unwrap_or(DEFAULT_LOG_LINES))
+    })
+        .describe("Shows the last lines the server sent")
+        .in_category("Output");
+    muncher.register_typed("search", Signature::new().variadic("text", ArgType::Text), |args| {
+        LocalAction::Search(args.rest::<String>("text").join(" "))
+    })
+        .describe("Finds text in the output, again for older matches, or clears the search without text")
+        .in_category("Output");
+    muncher.register_typed("help", Signature::new().optional("command", ArgType::Text), |args| LocalAction::Help(args.opt("command")))
+        .describe("Lists the client's commands, or explains one")
+        .in_category("Help");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_picks_local_commands() {
+        assert_eq!(classify("/quit", "/"), Input::Local("quit"));
+        assert_eq!(classify("look around", "/"), Input::Server("look around"));
+        assert_eq!(classify("//shrug", "/"), Input::Server("/shrug"));
+        assert_eq!(classify("!!help", "!!"), Input::Local("help"));
+        assert_eq!(classify("!!!!help", "!!"), Input::Server("!!help"));
+        assert_eq!(classify("/quit", ""), Input::Server("/quit"));
+    }
+}
@@ -1,26 +1,40 @@
-use better_term::style::Color;
-use std::net::TcpStream;
-use std::io::{Write, Read, stdin, stdout};
-use crate::network::entry_point_io::{write_entry_point_ver, write_entry_login_attempt};
-use crate::network::entry_response_io::read_entry_response;
+use better_term::style::{Color, Style};
+use std::io::{Write, stdin, stdout};
+use crate::network::handshake::HandshakeError;
 use crate::network::login_data::LoginData;
-use crate::network::event_io::{read_event, write_event_keepalive, write_event_message, write_event_error};
+use crate::network::server_event::ServerEvent;
+use crate::network::error::NetworkError;
+use crate::session::{SessionEvent, SessionEnd};
+use crate::config::{ClientConfig, ReconnectConfig};
+use crate::local::{Input, LocalAction, classify, register_local_commands};
+use crate::ui::{Console, PlainConsole, Ui, UiCommand, markup};
+use crate::history::History;
 use std::io;
-use std::process::Command;
-use crate::command::CommandMuncher;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+use crate::command::{CommandFailReason, CommandMuncher};
+use crate::command::policy::Policy;
+use crate::command::signature::{ArgType, Signature};
+use crate::servers::{ServerList, ServerEntry};
 use crossterm::{
     execute,
     cursor::MoveTo,
-    terminal::{Clear, size},
+    terminal::Clear,
 };
-use crossterm::terminal::{ClearType, SetSize};
-
-pub mod utils;
-pub mod packet_capnp;
-mod network;
-mod command;
-
-pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+use crossterm::terminal::{ClearType, enable_raw_mode, disable_raw_mode};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::tty::IsTty;
+use zeroize::{Zeroize, Zeroizing};
+use mini_mmo_client::{command, network, utils};
+use mini_mmo_client::client::Client;
+
+mod servers;
+mod session;
+mod config;
+mod local;
+mod history;
+mod ui;
 
 #[cfg(target_os = "linux")]
 pub const CLEAR: &str = "clear";
@@ -30,36 +44,11 @@ pub const CLEAR: &str = "cls";
 pub const CLEAR: &str = "clear";
 
 fn clear_term() {
-    execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0));
-}
-
-fn set_cursor_pos(x: u16, y: u16) {
-    execute!(stdout(), MoveTo(x, y));
-}
-
-fn get_term_size() -> (u16, u16) {
-    size().expect("Failed to get terminal size")
-}
-
-fn set_term_size(width: u16, height: u16) {
-    execute!(stdout(), SetSize(width, height));
+    let _ = execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0));
 }
 
-fn connection_err(ip: &str, port: &str) {
-    eprintln!("{}Failed to connect to the server.", Color::Red);
-}
-
-fn nom_data(data: String) -> Vec<String> {
-    let mut cmds = Vec::new();
-
-    data.replace("!;!", "\r");
-    let split = data.split(";");
-
-    for s in split {
-        cmds.push(s.replace("\r", ";"));
-    }
-
-    cmds
+fn connection_err(address: &str, e: &io::Error) {
+    eprintln!("{}Failed to connect to the server at {}: {}", Color::Red, address, e);
 }
 
 pub fn read_console() -> String {
@@ -70,227 +59,566 @@ pub fn read_console() -> String {
 
 pub fn get_input<S: Into<String>>(prompt: S) -> String {
     print!("{}", prompt.into());
-    io::stdout().flush();
+    let _ = io::stdout().flush();
     let read = read_console();
-    let input = read.replace("\n", "");
-    clear_term();
-    input
+    read.replace("\n", "")
 }
 
-fn main() {
-
-    // TODO: Add server selector and ability to create and remove servers
-    let ip = "localhost";
-    let port = "2277";
-    let address = format!("{}:{}", ip, port);
-
-    println!("Connecting to {}", address.clone());
+/// Reads a line without echoing it, for passwords. Returns None if the user cancels with Ctrl+C or Esc.
+/// Falls back to a normal read when the input isn't a terminal.
+pub fn read_password<S: Into<String>>(prompt: S) -> Option<String> {
+    print!("{}", prompt.into());
+    let _ = io::stdout().flush();
 
-    let mut stream_result = TcpStream::connect(address.clone());
-    if stream_result.is_err() {
-        connection_err(ip, port);
-        return;
+    if !stdin().is_tty() || enable_raw_mode().is_err() {
+        let read = read_console();
+        return Some(read.trim_end_matches(['\r', '\n']).to_string());
     }
 
-    let mut stream = stream_result.unwrap();
-
-    write_entry_point_ver(&stream, VERSION.to_string());
-
-    // TODO: Move ping to login and only have one connection
-
-    let (valid, _, server_version, err) = read_entry_response(&stream);
-    if server_version.is_some() {
-        //println!("Valid: {}\nserver version: {}", valid, server_version.unwrap());
-        if !valid {
-            println!("{}Your client is outdated! The server is running {} while you're still on {}! Please make sure to update!", Color::Red, server_version.unwrap(), VERSION);
-            return;
+    let mut passwd = String::new();
+    let entered = loop {
+        match read() {
+            Ok(Event::Key(KeyEvent { code: KeyCode::Enter, .. })) => break true,
+            Ok(Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers })) if modifiers.contains(KeyModifiers::CONTROL) => break false,
+            Ok(Event::Key(KeyEvent { code: KeyCode::Esc, .. })) => break false,
+            Ok(Event::Key(KeyEvent { code: KeyCode::Backspace, .. })) => {
+                passwd.pop();
+            }
+            Ok(Event::Key(KeyEvent { code: KeyCode::Char(c), .. })) => passwd.push(c),
+            Ok(_) => {}
+            Err(_) => break false,
         }
-    } else {
-        if err.is_some() {
-            println!("Valid: {}\nerror: {}", valid, err.unwrap());
-        } else {
-            connection_err(ip, port);
+    };
+    let _ = disable_raw_mode();
+    // the enter key isn't echoed in raw mode
+    println!();
+
+    if !entered {
+        passwd.zeroize();
+        return None;
+    }
+    Some(passwd)
+}
+
+/// Prompts the user for their login or signup details
+fn prompt_login(console: &mut dyn Console) -> Option<LoginData> {
+    let mut signup = false;
+    let mut email = String::new();
+
+    let mut su = String::new();
+    let mut loop_count: usize = 0;
+    // get if the user is signing up
+    while su != "y" && su != "n" && su != "yes" && su != "no" {
+        if loop_count != 0 {
+            console.say("Invalid response! Type 'y' for yes and 'n' for no.");
         }
-        return;
+        su = console.ask("Are you signing up? (y for yes and n for no): ", false)?;
+        loop_count += 1;
     }
-    drop(stream); // stop the ping connection
 
-    loop {
+    // if the user is signing up, get email
+    if su == "y" || su == "yes" {
+        signup = true;
+        email = console.ask("Enter your email: ", false)?;
+    }
 
-        // establish new connection
-        stream_result = TcpStream::connect(address.clone());
-        if stream_result.is_err() {
-            connection_err(ip, port);
-            return;
-        }
-        stream = stream_result.unwrap();
-
-        let mut signup = false;
-        let mut email = String::new();
-
-        let mut su = "";
-        let mut read = format!("");
-        let mut loop_count: usize = 0;
-        // get if the user is signing up
-        while su != "y" && su != "n" && su != "yes" && su != "no" {
-            if loop_count != 0 {
-                println!("Invalid response! Type 'y' for yes and 'n' for no.");
-            }
-            read = get_input("Are you signing up? (y for yes and n for no): ");
-            su = read.as_str();
-            loop_count += 1;
-        }
+    // get the username
+    let username = console.ask("Enter your username: ", false)?;
 
-        // if the user is signing up, get email
-        if su == "y" || su == "yes" {
-            signup = true;
-            email = get_input("Enter your email: ");
-        }
+    let mut passwd = Zeroizing::new(console.ask("Enter your password: ", true)?);
 
-        // get the username
-        let username = get_input("Enter your username: ");
+    if signup {
+        let mut pass_valid = false;
 
-        let mut passwd = get_input("Enter your password: ");
+        let mut password_check = Zeroizing::new(console.ask("Enter your password again: ", true)?);
 
-        if signup {
-            let mut pass_valid = false;
+        if passwd == password_check {
+            pass_valid = true;
+        }
+
+        // try until the passwords match
+        while !pass_valid {
+            console.say("The passwords did not match!");
 
-            let mut password_check = get_input("Enter your password again: ");
+            passwd = Zeroizing::new(console.ask("Enter your password: ", true)?);
+            password_check = Zeroizing::new(console.ask("Enter your password again: ", true)?);
 
             if passwd == password_check {
                 pass_valid = true;
             }
+        }
+    }
 
-            // try until the passwords match
-            while !pass_valid {
-                println!("The passwords did not match!");
+    Some(LoginData {
+        email,
+        username,
+        passwd: std::mem::take(&mut *passwd),
+        signup
+    })
+}
+
+/// Registers the commands the server can run through `Message.data`
+fn register_server_commands(muncher: &mut CommandMuncher<UiCommand>) {
+    muncher.register_typed("clear", Signature::new(), |_args| UiCommand::Clear)
+        .describe("Clears the output");
+    muncher.register_typed("cursor", Signature::new().arg("x", ArgType::U16).arg("y", ArgType::U16), |args| {
+        UiCommand::Cursor(args.get("x"), args.get("y"))
+    })
+        .describe("Moves the cursor, outside the full-screen interface");
+    muncher.register_typed("size", Signature::new().arg("width", ArgType::U16).arg("height", ArgType::U16), |args| {
+        UiCommand::Size(args.get("width"), args.get("height"))
+    })
+        .describe("Asks the terminal to resize itself");
+    muncher.register_typed("print", Signature::new().variadic("text", ArgType::Text), |args| {
+        UiCommand::Print(args.rest::<String>("text").join(" "))
+    })
+        .describe("Prints text without ending the line");
+    muncher.register_typed("println", Signature::new().variadic("text", ArgType::Text), |args| {
+        UiCommand::Println(args.rest::<String>("text").join(" "))
+    })
+        .describe("Prints a line");
+    muncher.register_typed("commands", Signature::new().variadic("usage", ArgType::Text), |args| {
+        UiCommand::Commands(args.rest::<String>("usage"))
+    })
+        .describe("Advertises the server's commands as usage lines, for completion");
+    muncher.register_typed("span", Signature::new().arg("style", ArgType::Text).variadic("text", ArgType::Text), |args| {
+        UiCommand::Span { style: args.get("style"), text: args.rest::<String>("text").join(" ") }
+    })
+        .describe("Prints text in a style like bold+red or italic+#ff8800+bg:236, without ending the line");
+    muncher.register_typed("box", Signature::new().arg("title", ArgType::Text).variadic("lines", ArgType::Text), |args| {
+        UiCommand::Box { title: args.get("title"), lines: args.rest("lines") }
+    })
+        .describe("Draws a border around lines, with a title in its top edge");
+    muncher.register_typed("table", Signature::new().arg("columns", ArgType::Text).variadic("rows", ArgType::Text), |args| {
+        UiCommand::Table { columns: args.get("columns"), rows: args.rest("rows") }
+    })
+        .describe("Draws a table of | separated cells, a column header starting with > is right aligned and ^ centered");
+    let bar = Signature::new()
+        .arg("label", ArgType::Text)
+        .arg("value", ArgType::F64)
+        .arg("max", ArgType::F64)
+        .optional("width", ArgType::U16)
+        .optional("color", ArgType::Text);
+    muncher.register_typed("bar", bar, |args| {
+        UiCommand::Bar { label: args.get("label"), value: args.get("value"), max: args.get("max"), width: args.opt("width"), color: args.opt("color") }
+    })
+        .describe("Draws a progress or health bar, colored by how full it is unless a color is given");
+    muncher.register_typed("rule", Signature::new().variadic("title", ArgType::Text), |args| {
+        UiCommand::Rule(args.rest::<String>("title").join(" "))
+    })
+        .describe("Draws a horizontal line across the output");
+    muncher.register_typed("title", Signature::new().variadic("text", ArgType::Text), |args| {
+        UiCommand::Title(args.rest::<String>("text").join(" "))
+    })
+        .describe("Sets the terminal's title");
+    muncher.register_typed("ui", Signature::new().arg("version", ArgType::U32), |args| UiCommand::Version(args.get("version")))
+        .describe("Tells the client which version of these commands the server uses");
+}
 
-                passwd = get_input("Enter your password: ");
-                password_check = get_input("Enter your password again: ");
+/// Connects, checks the version and logs in over a single connection.
+/// If a resume token is given the previous session is resumed, falling back to a normal login if the server rejects it.
+/// Returns the logged in client for the game session.
+fn establish_session(server: &ServerEntry, resume_token: Option<&str>, console: &mut dyn Console) -> Result<Client, HandshakeError> {
+    let tls = server.tls().map_err(|e| HandshakeError::Network(NetworkError::Io(io::Error::other(e))))?;
+    let store = tls.as_ref().map(|(_, store)| store.clone());
+    let mut client = Client::connect(server.ip.as_str(), server.port, tls)?;
+    if let Some(fingerprint) = store.and_then(|store| store.newly_pinned()) {
+        console.say(format!("Trusting the certificate for {} on first use ({})", server.address(), fingerprint).as_str());
+    }
 
-                if passwd == password_check {
-                    pass_valid = true;
+    let resumed = match resume_token {
+        Some(token) => match client.resume(token) {
+            Ok(logged_in) => Some(logged_in),
+            Err(HandshakeError::LoginRejected(reason)) => {
+                console.say(format!("Could not resume your session, please log in again.\n{}", reason).as_str());
+                None
+            }
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    match resumed {
+        Some(logged_in) => console.say(format!("Session resumed!\n{}", logged_in.motd).as_str()),
+        // the version was accepted, log in over the same connection
+        None => loop {
+            let login_data = prompt_login(console).ok_or(HandshakeError::Cancelled)?;
+
+            match client.login(login_data) {
+                Ok(logged_in) => {
+                    console.say(format!("Logged in successfully!\n{}", logged_in.motd).as_str());
+                    break;
                 }
+                // login was not valid, print the reason and retry
+                Err(e @ HandshakeError::LoginRejected(_)) => console.say(e.to_string().as_str()),
+                Err(e) => return Err(e),
             }
-        }
+        },
+    }
 
-        let login_data = LoginData {
-            email,
-            username,
-            passwd,
-            signup
-        };
+    Ok(client)
+}
 
-        // attempt login
-        write_entry_login_attempt(&stream, login_data);
-        let (login_valid, login_motd, _, login_err) = read_entry_response(&stream);
+/// The status line for the session with `server`
+fn status(server: &ServerEntry, state: &str) -> String {
+    format!(" {} ({}) | {}", server.name, server.address(), state)
+}
 
-        // if login was valid, print motd and exit login loop
-        if login_valid && login_motd.is_some() {
-            println!("Logged in successfully!\n{}", login_motd.unwrap());
-            break;
-        }
-        // login was not valid, print data and retry
-        println!("Login Attempt Failed.");
-        if login_err.is_some() {
-            println!("{}", login_err.unwrap());
+/// Keeps trying to re-establish the session with exponential backoff
+fn reconnect(server: &ServerEntry, config: &ReconnectConfig, resume_token: Option<&str>, ui: &mut Ui) -> Option<Client> {
+    for (attempt, delay) in config.backoff().enumerate() {
+        ui.set_status(status(server, format!("reconnecting (attempt {})", attempt + 1).as_str()));
+        ui.println(format!("Reconnecting in {:.1}s (attempt {})...", delay.as_secs_f64(), attempt + 1).as_str());
+        thread::sleep(delay);
+
+        // the session's interface owns the terminal now, so prompts have to go through it
+        match establish_session(server, resume_token, ui) {
+            Ok(session) => {
+                ui.set_status(status(server, "connected"));
+                return Some(session);
+            }
+            Err(HandshakeError::Network(e)) => ui.println(format!("Reconnect failed: {}", e).as_str()),
+            Err(e) => {
+                ui.error(e.to_string().as_str());
+                return None;
+            }
         }
-        drop(stream);
     }
+    None
+}
 
-    let mut muncher: CommandMuncher<Result<(), String>> = CommandMuncher::new();
-
-    muncher.register("clear", |args| {
-        clear_term();
-        Ok(())
-    });
-    muncher.register("cursor", |args| {
-        if args.len() < 2 {
-            return Err("Invalid parameters: cursor command takes two parameters of type u16".to_string());
-        }
-        let xs = args.get(0).unwrap();
-        let ys = args.get(1).unwrap();
+/// how long to wait for the server to acknowledge a logout
+const LOGOUT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// how many lines of server output `/log` can look back on
+const LOG_CAPACITY: usize = 500;
+
+/// Everything that outlives a single connection to the server
+struct SessionContext {
+    ui: Ui,
+    /// commands the server runs through `Message.data`
+    server_commands: CommandMuncher<UiCommand>,
+    /// commands the user runs with the command prefix
+    local_commands: CommandMuncher<LocalAction>,
+    prefix: String,
+    /// what the server's commands may do
+    policy: Policy,
+    /// the latest lines of server output, oldest first
+    log: VecDeque<String>,
+}
 
-        let xr = xs.parse::<u16>();
-        let yr = ys.parse::<u16>();
-        if xr.is_err() || yr.is_err() {
-            return Err("Invalid parameters: cursor command takes two parameters of type u16".to_string());
+impl SessionContext {
+    fn record(&mut self, text: &str) {
+        for line in text.lines() {
+            if self.log.len() == LOG_CAPACITY {
+                self.log.pop_front();
+            }
+            self.log.push_back(line.to_string());
         }
+    }
 
-        let x = xr.unwrap();
-        let y = yr.unwrap();
+    /// Tells the user about server commands the policy kept from running, and keeps them in the log
+    fn blocked(&mut self, text: &str) {
+        let text = format!("Blocked a server command: {}", text);
+        self.ui.error(text.as_str());
+        self.record(text.as_str());
+    }
 
-        set_cursor_pos(x, y);
-        Ok(())
-    });
-    muncher.register("size", |args| {
-        if args.len() < 2 {
-            return Err("Invalid parameters: size command takes two parameters of type u16".to_string());
+    /// Names local commands the way they are typed in a failure
+    fn with_prefix(&self, reason: CommandFailReason) -> CommandFailReason {
+        match reason {
+            CommandFailReason::InvalidCommand { name, suggestions } => CommandFailReason::InvalidCommand {
+                name: format!("{}{}", self.prefix, name),
+                suggestions: suggestions.iter().map(|suggestion| format!("{}{}", self.prefix, suggestion)).collect(),
+            },
+            reason => reason,
         }
-        let xs = args.get(0).unwrap();
-        let ys = args.get(1).unwrap();
+    }
+
+    /// Runs a local command. Returns the action if it ends the session, for the session to carry out
+    fn run_local(&mut self, command: &str) -> Option<LocalAction> {
+        let action = match self.local_commands.munch(command) {
+            Ok(action) => action,
+            Err(e) => {
+                let e = self.with_prefix(e);
+                self.ui.error(e.to_string().as_str());
+                return None;
+            }
+        };
 
-        let x = xs.parse::<u16>();
-        let y = ys.parse::<u16>();
-        if x.is_err() || y.is_err() {
-            return Err("Invalid parameters: size command takes two parameters of type u16".to_string());
+        match action {
+            LocalAction::Quit(_) | LocalAction::Reconnect => return Some(action),
+            LocalAction::Clear => self.ui.clear(),
+            LocalAction::ShowLog(lines) => {
+                let skip = self.log.len().saturating_sub(lines as usize);
+                for line in self.log.iter().skip(skip) {
+                    self.ui.println(line.as_str());
+                }
+            }
+            LocalAction::Help(Some(command)) => {
+                // the prefix is optional when asking about a command
+                let command = command.strip_prefix(self.prefix.as_str()).unwrap_or(command.as_str());
+                match self.local_commands.help(command) {
+                    Ok(help) => self.ui.println(format!("{}{}", self.prefix, help).as_str()),
+                    Err(e) => {
+                        let e = self.with_prefix(e);
+                        self.ui.error(e.to_string().as_str());
+                    }
+                }
+            }
+            LocalAction::Help(None) => {
+                self.ui.println(format!("Client commands (type {}{} to send a line starting with '{}' to the server):", self.prefix, self.prefix, self.prefix).as_str());
+                let mut lines = Vec::new();
+                let mut category = None;
+                for info in self.local_commands.list() {
+                    if category != Some(info.category.as_str()) {
+                        category = Some(info.category.as_str());
+                        lines.push(format!("{}:", info.category));
+                    }
+                    lines.push(format!("  {}{} - {}", self.prefix, info.usage, info.description));
+                }
+                for line in lines {
+                    self.ui.println(line.as_str());
+                }
+            }
+            LocalAction::Search(term) if self.ui.has_scrollback() => {
+                if let Err(e) = self.ui.search(term.as_str()) {
+                    self.ui.error(e.as_str());
+                }
+            }
+            // without a scrollback, list the matching lines of the log instead
+            LocalAction::Search(term) => {
+                let term = term.to_ascii_lowercase();
+                let matches = self.log.iter().filter(|line| line.to_ascii_lowercase().contains(term.as_str())).cloned().collect::<Vec<String>>();
+                for line in matches {
+                    self.ui.println(line.as_str());
+                }
+            }
+            LocalAction::Servers => match ServerList::load() {
+                Ok(servers) => {
+                    self.ui.println("Saved servers:");
+                    for line in servers.describe() {
+                        self.ui.println(format!("  {}", line).as_str());
+                    }
+                }
+                Err(e) => self.ui.error(e.as_str()),
+            },
         }
+        None
+    }
+}
 
-        let width = x.unwrap();
-        let height = y.unwrap();
+/// Logs out of the session, giving the server `reason`
+fn logout(client: &mut Client, ui: &mut Ui, reason: &str) -> SessionEnd {
+    ui.println("Logging out...");
+    match client.logout(reason, LOGOUT_TIMEOUT) {
+        Ok(true) => {}
+        Ok(false) => ui.println("The server did not answer, closing the connection anyway."),
+        Err(e) => ui.println(format!("Could not log out cleanly: {}", e).as_str()),
+    }
+    SessionEnd::Quit
+}
 
-        set_term_size(width, height);
-        Ok(())
-    });
-    muncher.register("print", |args| {
-        print!("{}", args.join(" "));
-        Ok(())
-    });
-    muncher.register("println", |args| {
-        println!("{}", args.join(" "));
-        Ok(())
-    });
+/// Runs the game session until it ends. Server events and user input are handled as they arrive.
+fn run_session(mut client: Client, ctx: &mut SessionContext) -> SessionEnd {
+    // server events arrive on the same channel as user input
+    client.forward_events(ctx.ui.sender());
 
-    // main loop
+    ctx.ui.request_line();
     loop {
-        // read an event
-        let (msg, time, error, disconnect_status) = read_event(&stream);
+        // wait for the next event, the interface keeps the channel open
+        let event = match ctx.ui.next_event() {
+            Some(event) => event,
+            None => return SessionEnd::Quit,
+        };
+
+        match event {
+            SessionEvent::Input(line) => {
+                match classify(line.as_str(), ctx.prefix.as_str()) {
+                    Input::Local(command) => match ctx.run_local(command) {
+                        Some(LocalAction::Quit(reason)) => return logout(&mut client, &mut ctx.ui, reason.as_str()),
+                        Some(LocalAction::Reconnect) => return SessionEnd::Reconnect,
+                        Some(_) | None => {}
+                    },
+                    Input::Server(message) => {
+                        // send the input to the server to process, with the size it has to draw in
+                        let (width, height) = ctx.ui.pane_size();
+                        let data = format!("{},{}", width, height);
+                        if let Err(e) = client.send(message.to_string(), data) {
+                            return SessionEnd::ConnectionLost(e);
+                        }
+                    }
+                }
+                ctx.ui.request_line();
+            }
+            SessionEvent::InputClosed => return logout(&mut client, &mut ctx.ui, "The client closed its input"),
+            SessionEvent::Interrupted => {
+                ctx.ui.println("");
+                return logout(&mut client, &mut ctx.ui, "The client was closed");
+            }
+            SessionEvent::Server(Ok(ServerEvent::Message(server_msg))) => {
+                // ======= GAME LOGIC =======
+
+                // print message from server
+                ctx.ui.print_markup(server_msg.message.as_str());
+                ctx.record(markup::plain(markup::strip_escapes(server_msg.message.as_str()).as_str()).as_str());
+
+                // Process the commands from the server
+                // a malformed stream runs nothing, rather than whatever parsed before the mistake
+                match command::grammar::parse(server_msg.data.as_str()) {
+                    Ok(cmds) => {
+                        let (cmds, dropped) = ctx.policy.limit(cmds);
+                        for cmd in cmds {
+                            match ctx.server_commands.munch_args_with(cmd, &ctx.policy) {
+                                Ok(ui_command) => ctx.ui.apply(ui_command),
+                                Err(e @ CommandFailReason::Blocked { .. }) => ctx.blocked(e.to_string().as_str()),
+                                Err(e) => ctx.ui.println(format!("Encountered error in munching command: {}", e).as_str()),
+                            }
+                        }
+                        if !dropped.is_empty() {
+                            let text = format!("{} more commands in the message were dropped, it may only run {}", dropped.len(), ctx.policy.max_per_message.unwrap_or_default());
+                            ctx.blocked(text.as_str());
+                        }
+                    }
+                    Err(e) => ctx.ui.println(format!("Received malformed commands from the server: {}", e).as_str()),
+                }
 
-        if msg.is_some() {
-            // ======= GAME LOGIC =======
+                ctx.ui.show_prompt();
+            }
+            // answered by the reader thread
+            SessionEvent::Server(Ok(ServerEvent::Keepalive(_))) => {}
+            SessionEvent::Server(Ok(ServerEvent::Error(error))) => {
+                ctx.ui.println(error.as_str());
+                ctx.record(error.as_str());
+            }
+            SessionEvent::Server(Ok(ServerEvent::Disconnected(reason))) => return SessionEnd::Kicked(reason),
+            SessionEvent::Server(Err(e @ NetworkError::NotInSchema(_))) | SessionEvent::Server(Err(e @ NetworkError::Decode(_))) => {
+                ctx.ui.println(format!("Invalid packet received from the server: {}", e).as_str());
+            }
+            SessionEvent::Server(Err(e)) => return SessionEnd::ConnectionLost(e),
+            // the interface handles these itself
+            SessionEvent::Terminal(_) | SessionEvent::InputCancelled => {}
+        }
+    }
+}
 
-            let server_msg = msg.unwrap();
+fn main() {
+    let config = match ClientConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}{}", Color::Red, e);
+            return;
+        }
+    };
 
-            // print message from server
-            print!("{}", server_msg.message);
+    let mut servers = match ServerList::load() {
+        Ok(servers) => servers,
+        Err(e) => {
+            eprintln!("{}{}", Color::Red, e);
+            return;
+        }
+    };
+
+    let server = match servers.select() {
+        Some(server) => server,
+        None => return,
+    };
+    let address = server.address();
+
+    println!("Connecting to {} ({})", server.name, address);
+
+    // the session's interface hasn't started yet, so the first login prompts on the terminal directly
+    let mut console = PlainConsole::default();
+    let mut client = match establish_session(&server, None, &mut console) {
+        Ok(session) => session,
+        Err(HandshakeError::Network(NetworkError::Io(e))) => {
+            connection_err(&address, &e);
+            return;
+        }
+        Err(e) => {
+            println!("{}{}", Color::Red, e);
+            return;
+        }
+    };
+
+    let mut server_commands = CommandMuncher::new();
+    register_server_commands(&mut server_commands);
+    let mut local_commands = CommandMuncher::new();
+    register_local_commands(&mut local_commands);
+
+    // the lines sent to this server in earlier sessions, for the input line to recall
+    let history = History::load(server.name.as_str()).unwrap_or_else(|e| {
+        eprintln!("{}{}{}", Color::Red, e, Style::default().overwrite());
+        History::new()
+    });
 
-            // Process the commands from the server
-            for cmd in nom_data(server_msg.data) {
-                let result = muncher.munch(cmd);
-                if result.is_err() {
-                    println!("Encountered error in munching command: {}", result.unwrap_err());
+    // the interface owns the terminal for the rest of the program, across reconnects
+    let mut ui = Ui::start(status(&server, "connected"), console.said.as_slice(), history);
+    ui.set_colors(config.display.colors.enabled());
+    if !config.commands.prefix.is_empty() {
+        ui.set_search_command(format!("{}search ", config.commands.prefix));
+        ui.set_local_commands(config.commands.prefix.as_str(), &local_commands);
+    }
+    // Ctrl+C and SIGTERM log out like /quit, instead of killing the process mid-session
+    let interrupts = ui.sender();
+    if let Err(e) = ctrlc::set_handler(move || { let _ = interrupts.send(SessionEvent::Interrupted); }) {
+        eprintln!("{}Could not handle Ctrl+C, it will close the client without logging out: {}", Color::Red, e);
+    }
+    let mut ctx = SessionContext {
+        ui,
+        server_commands,
+        local_commands,
+        prefix: config.commands.prefix.clone(),
+        policy: server.commands.clone(),
+        log: VecDeque::new(),
+    };
+
+    // printed once the terminal is back to normal, so it stays visible
+    let mut farewell = Vec::new();
+    loop {
+        // kept to resume the session if the connection drops
+        let resume_token = client.resume_token().map(String::from);
+        match run_session(client, &mut ctx) {
+            SessionEnd::Quit => break,
+            SessionEnd::Kicked(reason) => {
+                if !reason.is_empty() {
+                    farewell.push(reason);
                 }
+                farewell.push("You have been disconnected.".to_string());
+                break;
             }
-
-            // get input and send it to the server to process
-            let input = get_input("> ");
-            let term_size = get_term_size();
-            let data = format!("{},{}", term_size.0, term_size.1);
-            write_event_message(&stream, input, data);
-        } else if time.is_some() {
-            write_event_keepalive(&stream);
-        } else {
-            if error.is_some() {
-                println!("{}", error.unwrap());
-            } else {
-                println!("Invalid packet received from the server.");
+            SessionEnd::Reconnect => {
+                // the old client is gone, so the server sees this connection close
+                ctx.ui.println(format!("Reconnecting to {}...", server.name).as_str());
+                match reconnect(&server, &config.reconnect, resume_token.as_deref(), &mut ctx.ui) {
+                    Some(new_client) => client = new_client,
+                    None => {
+                        farewell.push("Could not reconnect to the server. You have been disconnected.".to_string());
+                        break;
+                    }
+                }
             }
-        }
+            SessionEnd::ConnectionLost(e) => {
+                ctx.ui.println(format!("\n{}", e).as_str());
+                if !config.reconnect.enabled {
+                    farewell.push(e.to_string());
+                    farewell.push("You have been disconnected.".to_string());
+                    break;
+                }
 
-        if disconnect_status {
-            println!("You have been disconnected.");
-            break;
+                match reconnect(&server, &config.reconnect, resume_token.as_deref(), &mut ctx.ui) {
+                    Some(new_client) => client = new_client,
+                    None => {
+                        farewell.push("Could not reconnect to the server. You have been disconnected.".to_string());
+                        break;
+                    }
+                }
+            }
         }
     }
 
+    ctx.ui.leave();
+    for line in farewell {
+        println!("{}", line);
+    }
 }
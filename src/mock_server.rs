@@ -0,0 +1,343 @@
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use hashbrown::{HashMap, HashSet};
+use serde::Deserialize;
+use crate::network::entry_point::EntryPoint;
+use crate::network::entry_point_io::read_entry_point;
+use crate::network::entry_response_io::{write_challenge_entry_response, write_invalid_entry_response, write_ping_entry_response, write_valid_entry_response};
+use crate::network::error::NetworkError;
+use crate::network::event_io::{read_event, write_event_error, write_event_keepalive, write_event_message};
+use crate::network::scram::{self, AuthChallenge, Verifier, MIN_ITERATIONS, NONCE_LEN};
+use crate::network::server_event::ServerEvent;
+use crate::network::transport::Transport;
+
+/// Describes how the mock server behaves, loaded from a TOML fixture file
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Fixture {
+    /// the version clients have to match
+    pub version: String,
+    pub motd: String,
+    /// whether new accounts can be created
+    pub allow_signup: bool,
+    /// the accounts that can log in
+    #[serde(rename = "user")]
+    pub users: Vec<FixtureUser>,
+    /// events played to every client once it has logged in
+    pub script: Vec<ScriptStep>,
+    /// canned answers to client messages
+    #[serde(rename = "reply")]
+    pub replies: Vec<Reply>,
+    /// echo client messages that have no reply back to them
+    pub echo: bool,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            version: crate::VERSION.to_string(),
+            motd: "Welcome to the mock server!".to_string(),
+            allow_signup: true,
+            users: Vec::new(),
+            script: Vec::new(),
+            replies: Vec::new(),
+            echo: true,
+        }
+    }
+}
+
+impl Fixture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(raw.as_str())
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FixtureUser {
+    pub username: String,
+    pub password: String,
+}
+
+/// A single step of the scripted event sequence
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptStep {
+    /// sends a message, with the commands in `data` for the client to run
+    Message {
+        message: String,
+        #[serde(default)]
+        data: String,
+    },
+    /// sends an error, ending the session if `disconnect` is set
+    Error {
+        error: String,
+        #[serde(default)]
+        disconnect: bool,
+    },
+    /// sends a keepalive, the client should answer it
+    Keepalive,
+    /// pauses before the next step
+    Wait { ms: u64 },
+    /// ends the session with a reason
+    Disconnect {
+        #[serde(default)]
+        reason: String,
+    },
+}
+
+/// Answers a client message that matches `input` exactly
+#[derive(Deserialize, Clone, Debug)]
+pub struct Reply {
+    pub input: String,
+    pub message: String,
+    #[serde(default)]
+    pub data: String,
+}
+
+/// Accounts and sessions, shared between the connections
+struct State {
+    verifiers: HashMap<String, Verifier>,
+    resume_tokens: HashSet<String>,
+}
+
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// A scriptable stand-in for the game server, speaking the same protocol.
+/// Clones share the same accounts, sessions and log.
+#[derive(Clone)]
+pub struct MockServer {
+    fixture: Arc<Fixture>,
+    state: Arc<Mutex<State>>,
+    log: Arc<Mutex<Vec<String>>>,
+    on_log: Option<LogSink>,
+}
+
+impl MockServer {
+    pub fn new(fixture: Fixture) -> Self {
+        let verifiers = fixture.users.iter()
+            .filter_map(|user| Verifier::new(user.password.as_str(), MIN_ITERATIONS).ok().map(|v| (user.username.clone(), v)))
+            .collect();
+
+        Self {
+            fixture: Arc::new(fixture),
+            state: Arc::new(Mutex::new(State { verifiers, resume_tokens: HashSet::new() })),
+            log: Arc::new(Mutex::new(Vec::new())),
+            on_log: None,
+        }
+    }
+
+    /// Passes every line to `on_log` as it is logged, for watching the server while it runs
+    pub fn on_log<F: Fn(&str) + Send + Sync + 'static>(mut self, on_log: F) -> Self {
+        self.on_log = Some(Arc::new(on_log));
+        self
+    }
+
+    /// Everything the server has logged so far
+    pub fn log(&self) -> Vec<String> {
+        self.log.lock().map(|log| log.clone()).unwrap_or_default()
+    }
+
+    fn log_line(&self, line: String) {
+        if let Some(on_log) = &self.on_log {
+            on_log(line.as_str());
+        }
+        if let Ok(mut log) = self.log.lock() {
+            log.push(line);
+        }
+    }
+
+    /// Accepts clients until the listener fails, each on its own thread
+    pub fn serve(&self, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    self.log_line(format!("Failed to accept a client: {}", e));
+                    continue;
+                }
+            };
+            let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_else(|_| "unknown".to_string());
+            let server = self.clone();
+            thread::spawn(move || {
+                server.log_line(format!("[{}] connected", peer));
+                match server.handle(stream) {
+                    Ok(()) => server.log_line(format!("[{}] disconnected", peer)),
+                    Err(e) => server.log_line(format!("[{}] disconnected: {}", peer, e)),
+                }
+            });
+        }
+    }
+
+    /// Serves clients from `listener` on a background thread
+    pub fn spawn(&self, listener: TcpListener) -> JoinHandle<()> {
+        let server = self.clone();
+        thread::spawn(move || server.serve(listener))
+    }
+
+    /// Runs the entry handshake with a client, then plays the script and answers its messages
+    pub fn handle<S: Transport>(&self, mut stream: S) -> Result<(), NetworkError> {
+        if !self.entry(&mut stream)? {
+            return Ok(());
+        }
+
+        for step in self.fixture.script.iter() {
+            match step {
+                ScriptStep::Message { message, data } => write_event_message(&mut stream, message.as_str(), data.as_str())?,
+                ScriptStep::Error { error, disconnect } => {
+                    write_event_error(&mut stream, error.as_str(), *disconnect)?;
+                    if *disconnect {
+                        return Ok(());
+                    }
+                }
+                ScriptStep::Keepalive => write_event_keepalive(&mut stream)?,
+                ScriptStep::Wait { ms } => thread::sleep(Duration::from_millis(*ms)),
+                ScriptStep::Disconnect { reason } => {
+                    write_event_error(&mut stream, reason.as_str(), true)?;
+                    return Ok(());
+                }
+            }
+        }
+
+        loop {
+            match read_event(&mut stream) {
+                Ok(ServerEvent::Message(msg)) => {
+                    let reply = self.fixture.replies.iter().find(|r| r.input == msg.message);
+                    match reply {
+                        Some(reply) => write_event_message(&mut stream, reply.message.as_str(), reply.data.as_str())?,
+                        None if self.fixture.echo => write_event_message(&mut stream, format!("You said: {}\n", msg.message), String::new())?,
+                        None => {}
+                    }
+                }
+                Ok(ServerEvent::Keepalive(_)) => self.log_line("Keepalive answered".to_string()),
+                Ok(ServerEvent::Error(error)) => self.log_line(format!("Client sent an error: {}", error)),
+                Ok(ServerEvent::Disconnected(reason)) => {
+                    self.log_line(format!("Client left: {}", reason));
+                    // acknowledge, so the client knows it can close the connection
+                    write_event_error(&mut stream, "Goodbye!", true)?;
+                    return Ok(());
+                }
+                Err(NetworkError::Closed) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Answers entry points until the client logs in. Returns false if the client left first
+    fn entry<S: Transport>(&self, stream: &mut S) -> Result<bool, NetworkError> {
+        loop {
+            let entry_point = match read_entry_point(&mut *stream) {
+                Ok(entry_point) => entry_point,
+                Err(NetworkError::Closed) => return Ok(false),
+                Err(e) => return Err(e),
+            };
+
+            match entry_point {
+                EntryPoint::Version(version) => {
+                    let valid = version == self.fixture.version;
+                    write_ping_entry_response(&mut *stream, valid, self.fixture.version.clone())?;
+                }
+                EntryPoint::AuthStart { username, client_nonce } => {
+                    if self.authenticate(stream, username, client_nonce)? {
+                        return Ok(true);
+                    }
+                }
+                EntryPoint::Signup { username, verifier, .. } => {
+                    let created = {
+                        let mut state = self.lock_state();
+                        if !self.fixture.allow_signup {
+                            Err("Signups are closed")
+                        } else if state.verifiers.contains_key(&username) {
+                            Err("That username is taken")
+                        } else {
+                            state.verifiers.insert(username.clone(), verifier);
+                            Ok(())
+                        }
+                    };
+                    match created {
+                        Ok(()) => {
+                            self.log_line(format!("Signed up {}", username));
+                            self.logged_in(stream, None)?;
+                            return Ok(true);
+                        }
+                        Err(reason) => write_invalid_entry_response(&mut *stream, reason)?,
+                    }
+                }
+                EntryPoint::Resume(token) => {
+                    let known = self.lock_state().resume_tokens.remove(&token);
+                    if known {
+                        self.logged_in(stream, None)?;
+                        return Ok(true);
+                    }
+                    write_invalid_entry_response(&mut *stream, "Your session has expired")?;
+                }
+                EntryPoint::Login(_) => {
+                    write_invalid_entry_response(&mut *stream, "Plaintext logins are not supported, please update your client")?;
+                }
+                EntryPoint::AuthProof { .. } => {
+                    write_invalid_entry_response(&mut *stream, "No login is in progress")?;
+                }
+            }
+        }
+    }
+
+    /// Challenges the client and checks its proof. Returns true if it logged in
+    fn authenticate<S: Transport>(&self, stream: &mut S, username: String, client_nonce: Vec<u8>) -> Result<bool, NetworkError> {
+        let (salt, iterations) = match self.lock_state().verifiers.get(&username) {
+            Some(verifier) => (verifier.salt.clone(), verifier.iterations),
+            None => {
+                write_invalid_entry_response(&mut *stream, "Wrong username or password")?;
+                return Ok(false);
+            }
+        };
+
+        let mut nonce = client_nonce.clone();
+        nonce.extend(scram::random_bytes(NONCE_LEN).map_err(|e| NetworkError::Io(std::io::Error::other(e)))?);
+        let challenge = AuthChallenge { salt, iterations, nonce };
+        write_challenge_entry_response(&mut *stream, &challenge)?;
+
+        let proof = match read_entry_point(&mut *stream)? {
+            EntryPoint::AuthProof { nonce, proof } if nonce == challenge.nonce => proof,
+            _ => {
+                write_invalid_entry_response(&mut *stream, "Expected a proof for the challenge")?;
+                return Ok(false);
+            }
+        };
+
+        let message = scram::auth_message(username.as_str(), client_nonce.as_slice(), &challenge);
+        let signature = self.lock_state().verifiers.get(&username)
+            .and_then(|verifier| scram::verify_proof(verifier, message.as_slice(), proof.as_slice()));
+        match signature {
+            Some(signature) => {
+                self.log_line(format!("Logged in {}", username));
+                self.logged_in(stream, Some(signature.as_slice()))?;
+                Ok(true)
+            }
+            None => {
+                write_invalid_entry_response(&mut *stream, "Wrong username or password")?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Accepts the login with the MOTD and a fresh resume token
+    fn logged_in<S: Transport>(&self, stream: &mut S, server_signature: Option<&[u8]>) -> Result<(), NetworkError> {
+        let token = scram::random_bytes(16)
+            .map(|bytes| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+            .map_err(|e| NetworkError::Io(std::io::Error::other(e)))?;
+        self.lock_state().resume_tokens.insert(token.clone());
+        write_valid_entry_response(&mut *stream, self.fixture.motd.clone(), Some(token.as_str()), server_signature)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, State> {
+        // a panicking connection thread can't leave the maps half updated
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -2,4 +2,15 @@ pub mod login_data;
 pub mod event_io;
 pub mod entry_point_io;
 pub mod entry_response_io;
-pub mod message;
\ No newline at end of file
+pub mod message;
+pub mod handshake;
+pub mod error;
+pub mod server_event;
+pub mod entry_point;
+pub mod entry_response;
+pub mod event_reader;
+pub mod backoff;
+pub mod tls;
+pub mod transport;
+pub mod connection;
+pub mod scram;
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+/// Exponential backoff, yields the delay to wait before each retry
+pub struct Backoff {
+    next: Duration,
+    max: Duration,
+    multiplier: f64,
+    attempts_left: Option<u32>,
+}
+
+impl Backoff {
+    /// `max_attempts` of None retries forever
+    pub fn new(initial: Duration, max: Duration, multiplier: f64, max_attempts: Option<u32>) -> Self {
+        Self {
+            next: initial.min(max),
+            max,
+            multiplier: multiplier.max(1.0),
+            attempts_left: max_attempts,
+        }
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if let Some(left) = self.attempts_left.as_mut() {
+            if *left == 0 {
+                return None;
+            }
+            *left -= 1;
+        }
+
+        let delay = self.next;
+        self.next = Duration::try_from_secs_f64(self.next.as_secs_f64() * self.multiplier)
+            .unwrap_or(self.max)
+            .min(self.max);
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(delays: impl Iterator<Item = Duration>) -> Vec<u128> {
+        delays.map(|delay| delay.as_millis()).collect()
+    }
+
+    #[test]
+    fn delays_grow_up_to_the_max() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000), 2.0, Some(6));
+        assert_eq!(millis(backoff), vec![100, 200, 400, 800, 1000, 1000]);
+
+        // the first delay is capped as well, and the delay never shrinks
+        let backoff = Backoff::new(Duration::from_millis(500), Duration::from_millis(300), 0.5, Some(3));
+        assert_eq!(millis(backoff), vec![300, 300, 300]);
+    }
+
+    #[test]
+    fn attempts_run_out() {
+        assert_eq!(Backoff::new(Duration::from_millis(1), Duration::from_millis(1), 1.0, Some(0)).count(), 0);
+        assert_eq!(Backoff::new(Duration::from_millis(1), Duration::from_millis(1), 1.0, Some(3)).count(), 3);
+        // without a limit it keeps going, even once the delay overflows
+        let forever = Backoff::new(Duration::from_secs(1), Duration::MAX, 1e300, None);
+        assert_eq!(forever.take(1000).last(), Some(Duration::MAX));
+    }
+}
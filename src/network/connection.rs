@@ -0,0 +1,79 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use crate::network::tls::{TlsConfig, TlsStream, FingerprintStore};
+use crate::network::transport::Transport;
+
+/// A connection to the server, either plain TCP or wrapped in TLS.
+/// Clones share the same underlying connection, so one can read while another writes.
+pub enum Connection {
+    Plain(TcpStream),
+    Tls(TlsStream),
+}
+
+impl Connection {
+    /// Connects to `host:port`, running the TLS handshake first if `tls` is set
+    pub fn connect(host: &str, port: u16, tls: Option<(&TlsConfig, FingerprintStore)>) -> io::Result<Self> {
+        let stream = TcpStream::connect((host, port))?;
+        match tls {
+            Some((config, store)) => Ok(Connection::Tls(TlsStream::connect(stream, host, port, config, store)?)),
+            None => Ok(Connection::Plain(stream)),
+        }
+    }
+}
+
+impl Transport for Connection {
+    fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Connection::Plain(stream) => Ok(Connection::Plain(Transport::try_clone(stream)?)),
+            Connection::Tls(stream) => Ok(Connection::Tls(Transport::try_clone(stream)?)),
+        }
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => Transport::shutdown(stream),
+            Connection::Tls(stream) => Transport::shutdown(stream),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl Read for &Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => (&*stream).read(buf),
+            Connection::Tls(stream) => (&*stream).read(buf),
+        }
+    }
+}
+
+impl Write for &Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => (&*stream).write(buf),
+            Connection::Tls(stream) => (&*stream).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => (&*stream).flush(),
+            Connection::Tls(stream) => (&*stream).flush(),
+        }
+    }
+}
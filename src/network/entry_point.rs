@@ -0,0 +1,17 @@
+use crate::network::login_data::LoginData;
+use crate::network::scram::Verifier;
+
+/// The first packets a client sends, before the game session starts
+pub enum EntryPoint {
+    Version(String),
+    /// a plaintext login, only decoded so servers can turn it away
+    Login(LoginData),
+    /// a resume token from a previous login, to continue that session after reconnecting
+    Resume(String),
+    /// starts a challenge-response login for a user
+    AuthStart { username: String, client_nonce: Vec<u8> },
+    /// answers the server's challenge, proving the client knows the password
+    AuthProof { nonce: Vec<u8>, proof: Vec<u8> },
+    /// creates an account from a verifier derived from the password
+    Signup { email: String, username: String, verifier: Verifier },
+}
@@ -1,62 +1,117 @@
-use capnp::message::{TypedReader, Builder, HeapAllocator};
-use crate::packet_capnp::{entry_point, login};
-use std::borrow::Borrow;
-use capnp::serialize;
-use std::net::TcpStream;
+use capnp::message::Builder;
+use crate::packet_capnp::entry_point;
+use std::io::{Read, Write};
 use crate::network::login_data::LoginData;
+use crate::network::entry_point::EntryPoint;
+use crate::network::scram::Verifier;
+use crate::network::error::{NetworkError, read_message, write_message};
 
-pub fn write_entry_point_ver(mut stream: &TcpStream, version: String) -> ::capnp::Result<()> {
+pub fn write_entry_point_ver<W: Write>(stream: W, version: String) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut ep = message.init_root::<entry_point::Builder>();
         ep.set_version(version.as_str());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-pub fn write_entry_login_attempt(mut stream: &TcpStream, login_data: LoginData) -> ::capnp::Result<()> {
+/// Starts a challenge-response login, the server answers with a challenge
+pub fn write_entry_auth_start<W: Write>(stream: W, username: &str, client_nonce: &[u8]) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
-        let mut ep = message.init_root::<entry_point::Builder>();
-        let mut login = ep.init_login_attempt();
-        login.set_email(login_data.email.as_str());
-        login.set_username(login_data.username.as_str());
-        login.set_password(login_data.passwd.as_str());
-        login.set_signup(login_data.signup);
+        let ep = message.init_root::<entry_point::Builder>();
+        let mut start = ep.init_auth_start();
+        start.set_username(username);
+        start.set_client_nonce(client_nonce);
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-/// Returns LoginData, version, error
-pub fn read_entry_point(mut stream: &TcpStream) -> (Option<LoginData>, Option<String>, Option<String>) {
-    let msg_reader_raw = serialize::read_message(&mut stream, ::capnp::message::ReaderOptions::new());
-    if msg_reader_raw.is_err() {
-        return (None, None, Some(format!("Client disconnected while expecting message")));
+/// Answers the server's challenge with a proof of the password
+pub fn write_entry_auth_proof<W: Write>(stream: W, nonce: &[u8], proof: &[u8]) -> Result<(), NetworkError> {
+    let mut message = Builder::new_default();
+    {
+        let ep = message.init_root::<entry_point::Builder>();
+        let mut auth_proof = ep.init_auth_proof();
+        auth_proof.set_nonce(nonce);
+        auth_proof.set_proof(proof);
     }
-    let message_reader = msg_reader_raw.unwrap();
-    let ep_raw = message_reader.get_root::<entry_point::Reader>();
-    if ep_raw.is_err() {
-        return (None, None, Some(format!("Client disconnected while expecting message")));
+    write_message(stream, &message)
+}
+
+/// Creates an account, sending the verifier derived from the password instead of the password itself
+pub fn write_entry_signup<W: Write>(stream: W, email: &str, username: &str, verifier: &Verifier) -> Result<(), NetworkError> {
+    let mut message = Builder::new_default();
+    {
+        let ep = message.init_root::<entry_point::Builder>();
+        let mut signup = ep.init_signup();
+        signup.set_email(email);
+        signup.set_username(username);
+        signup.set_salt(verifier.salt.as_slice());
+        signup.set_iterations(verifier.iterations);
+        signup.set_stored_key(verifier.stored_key.as_slice());
+        signup.set_server_key(verifier.server_key.as_slice());
     }
-    let ep = ep_raw.unwrap();
+    write_message(stream, &message)
+}
 
-    return match ep.which() {
-        Ok(entry_point::LoginAttempt(login_data)) => {
-            let raw_ld = login_data.unwrap();
-            let ld = LoginData {
-                email: raw_ld.get_email().unwrap().to_string(),
-                username: raw_ld.get_username().unwrap().to_string(),
-                passwd: raw_ld.get_password().unwrap().to_string(),
+/// Asks the server to continue a previous session instead of logging in again
+pub fn write_entry_resume<W: Write>(stream: W, resume_token: &str) -> Result<(), NetworkError> {
+    let mut message = Builder::new_default();
+    {
+        let mut ep = message.init_root::<entry_point::Builder>();
+        ep.set_resume(resume_token);
+    }
+    write_message(stream, &message)
+}
+
+/// Reads the entry point a client sent
+pub fn read_entry_point<R: Read>(stream: R) -> Result<EntryPoint, NetworkError> {
+    let message_reader = read_message(stream)?;
+    let ep = message_reader.get_root::<entry_point::Reader>()?;
+
+    match ep.which()? {
+        entry_point::LoginAttempt(login_data) => {
+            let raw_ld = login_data?;
+            Ok(EntryPoint::Login(LoginData {
+                email: raw_ld.get_email()?.to_string(),
+                username: raw_ld.get_username()?.to_string(),
+                passwd: raw_ld.get_password()?.to_string(),
                 signup: raw_ld.get_signup(),
-            };
-            (Some(ld), None, None)
+            }))
         }
-        Ok(entry_point::Version(ver)) => {
-            (None, Some(ver.unwrap().to_string()), None)
+        entry_point::Version(ver) => {
+            Ok(EntryPoint::Version(ver?.to_string()))
         }
-        Err(::capnp::NotInSchema(_)) => {
-            // todo: error
-            (None, None, Some(String::from("Invalid EntryPoint - no version or login data found!")))
+        entry_point::Resume(token) => {
+            Ok(EntryPoint::Resume(token?.to_string()))
+        }
+        entry_point::AuthStart(start) => {
+            let start = start?;
+            Ok(EntryPoint::AuthStart {
+                username: start.get_username()?.to_string(),
+                client_nonce: start.get_client_nonce()?.to_vec(),
+            })
+        }
+        entry_point::AuthProof(auth_proof) => {
+            let auth_proof = auth_proof?;
+            Ok(EntryPoint::AuthProof {
+                nonce: auth_proof.get_nonce()?.to_vec(),
+                proof: auth_proof.get_proof()?.to_vec(),
+            })
+        }
+        entry_point::Signup(signup) => {
+            let signup = signup?;
+            Ok(EntryPoint::Signup {
+                email: signup.get_email()?.to_string(),
+                username: signup.get_username()?.to_string(),
+                verifier: Verifier {
+                    salt: signup.get_salt()?.to_vec(),
+                    iterations: signup.get_iterations(),
+                    stored_key: signup.get_stored_key()?.to_vec(),
+                    server_key: signup.get_server_key()?.to_vec(),
+                },
+            })
         }
     }
-}
\ No newline at end of file
+}
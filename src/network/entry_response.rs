@@ -0,0 +1,15 @@
+use crate::network::scram::AuthChallenge;
+
+/// The server's response to an entry point
+pub enum EntryResponse {
+    /// response to a version ping, `valid` is false if the client is outdated
+    Version { valid: bool, version: String },
+    /// the login attempt was accepted. The resume token, if the server issued one,
+    /// can be presented after reconnecting to continue the session.
+    /// After a challenge-response login the server signature proves the server knows the password's verifier
+    Motd { motd: String, resume_token: Option<String>, server_signature: Option<Vec<u8>> },
+    /// the salt, iteration count and nonce to prove knowledge of the password with
+    Challenge(AuthChallenge),
+    /// the login attempt was rejected, or the server could not process the entry point
+    Error(String),
+}
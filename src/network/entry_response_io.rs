@@ -1,64 +1,96 @@
-use capnp::message::{TypedReader, Builder, HeapAllocator};
+use capnp::message::Builder;
 use crate::packet_capnp::entry_response;
-use std::borrow::Borrow;
-use capnp::serialize;
-use std::net::TcpStream;
+use std::io::{Read, Write};
+use crate::network::entry_response::EntryResponse;
+use crate::network::scram::AuthChallenge;
+use crate::network::error::{NetworkError, read_message, write_message};
 
-pub fn write_valid_entry_response(mut stream: &TcpStream, motd: String) -> ::capnp::Result<()> {
+pub fn write_valid_entry_response<W: Write>(stream: W, motd: String, resume_token: Option<&str>, server_signature: Option<&[u8]>) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut er = message.init_root::<entry_response::Builder>();
         er.set_valid(true);
         er.set_motd(motd.as_str());
+        if let Some(token) = resume_token {
+            er.set_resume_token(token);
+        }
+        if let Some(signature) = server_signature {
+            er.set_server_signature(signature);
+        }
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-pub fn write_invalid_entry_response<S: Into<String>>(mut stream: &TcpStream, err: S) -> ::capnp::Result<()> {
+/// Challenges a client that started a login to prove it knows the password
+pub fn write_challenge_entry_response<W: Write>(stream: W, challenge: &AuthChallenge) -> Result<(), NetworkError> {
+    let mut message = Builder::new_default();
+    {
+        let er = message.init_root::<entry_response::Builder>();
+        let mut c = er.init_challenge();
+        c.set_salt(challenge.salt.as_slice());
+        c.set_iterations(challenge.iterations);
+        c.set_nonce(challenge.nonce.as_slice());
+    }
+    write_message(stream, &message)
+}
+
+pub fn write_invalid_entry_response<W: Write, S: Into<String>>(stream: W, err: S) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut er = message.init_root::<entry_response::Builder>();
         er.set_valid(false);
         er.set_error(err.into().as_str());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-pub fn write_ping_entry_response(mut stream: &TcpStream, client_valid: bool, version: String) -> ::capnp::Result<()> {
+pub fn write_ping_entry_response<W: Write>(stream: W, client_valid: bool, version: String) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut er = message.init_root::<entry_response::Builder>();
         er.set_valid(client_valid);
         er.set_version(version.as_str());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-/// returns valid, motd, version, error
-pub fn read_entry_response(mut stream: &TcpStream) -> (bool, Option<String>, Option<String>, Option<String>) {
-    let message_reader_result = serialize::read_message(&mut stream, ::capnp::message::ReaderOptions::new());
-    if message_reader_result.is_err() {
-        return (false, None, None, Some(String::from("Could not connect to server.")));
-    }
-    let message_reader = message_reader_result.unwrap();
-    let er_raw = message_reader.get_root::<entry_response::Reader>();
-    if er_raw.is_err() {
-        return (false, None, None, Some(String::from("Could not connect to server.")));
-    }
-    let er = er_raw.unwrap();
+/// Reads the server's response to an entry point.
+/// A MOTD that isn't marked valid is treated as an error.
+pub fn read_entry_response<R: Read>(stream: R) -> Result<EntryResponse, NetworkError> {
+    let message_reader = read_message(stream)?;
+    let er = message_reader.get_root::<entry_response::Reader>()?;
+    let valid = er.get_valid();
 
-    return match er.which() {
-        Ok(entry_response::Version(v)) => {
-            (er.get_valid(), None, Some(v.unwrap().to_string()), None)
+    match er.which()? {
+        entry_response::Version(v) => {
+            Ok(EntryResponse::Version { valid, version: v?.to_string() })
         }
-        Ok(entry_response::Motd(motd)) => {
-            (er.get_valid(), Some(motd.unwrap().to_string()), None, None)
+        entry_response::Motd(motd) if valid => {
+            let resume_token = if er.has_resume_token() {
+                Some(er.get_resume_token()?.to_string())
+            } else {
+                None
+            };
+            let server_signature = if er.has_server_signature() {
+                Some(er.get_server_signature()?.to_vec())
+            } else {
+                None
+            };
+            Ok(EntryResponse::Motd { motd: motd?.to_string(), resume_token, server_signature })
         }
-        Ok(entry_response::Error(err)) => {
-            (er.get_valid(), None, None, Some(err.unwrap().to_string()))
+        entry_response::Motd(motd) => {
+            Ok(EntryResponse::Error(motd?.to_string()))
         }
-        Err(::capnp::NotInSchema(_)) => {
-            (false, None, None, Some(String::from("Invalid EntryResponse - no data found!")))
+        entry_response::Error(err) => {
+            Ok(EntryResponse::Error(err?.to_string()))
+        }
+        entry_response::Challenge(challenge) => {
+            let challenge = challenge?;
+            Ok(EntryResponse::Challenge(AuthChallenge {
+                salt: challenge.get_salt()?.to_vec(),
+                iterations: challenge.get_iterations(),
+                nonce: challenge.get_nonce()?.to_vec(),
+            }))
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use capnp::message::{Allocator, Builder, Reader, ReaderOptions};
+use capnp::serialize::{self, OwnedSegments};
+use zeroize::Zeroize;
+
+/// Everything that can go wrong reading or writing a packet
+#[derive(Debug)]
+pub enum NetworkError {
+    /// the other side closed the connection between packets
+    Closed,
+    /// reading from or writing to the stream failed
+    Io(io::Error),
+    /// the packet could not be decoded
+    Decode(capnp::Error),
+    /// the packet used a union variant this client doesn't know about
+    NotInSchema(u16),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::Closed => write!(f, "The connection was closed"),
+            NetworkError::Io(e) => write!(f, "Connection error: {}", e),
+            NetworkError::Decode(e) => write!(f, "Failed to decode packet: {}", e.description),
+            NetworkError::NotInSchema(variant) => write!(f, "Received a packet with unknown variant {}", variant),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<io::Error> for NetworkError {
+    fn from(e: io::Error) -> Self {
+        NetworkError::Io(e)
+    }
+}
+
+impl From<capnp::Error> for NetworkError {
+    fn from(e: capnp::Error) -> Self {
+        NetworkError::Decode(e)
+    }
+}
+
+impl From<capnp::NotInSchema> for NetworkError {
+    fn from(e: capnp::NotInSchema) -> Self {
+        NetworkError::NotInSchema(e.0)
+    }
+}
+
+/// Remembers the last I/O error, since capnp flattens them into its own error type
+struct TrackingReader<R> {
+    inner: R,
+    last_error: Option<io::Error>,
+}
+
+impl<R: Read> Read for TrackingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).inspect_err(|e| {
+            self.last_error = Some(io::Error::new(e.kind(), e.to_string()));
+        })
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf).inspect_err(|e| {
+            self.last_error = Some(io::Error::new(e.kind(), e.to_string()));
+        })
+    }
+}
+
+/// Reads a single packet from the stream
+pub fn read_message<R: Read>(stream: R) -> Result<Reader<OwnedSegments>, NetworkError> {
+    let mut reader = TrackingReader { inner: stream, last_error: None };
+    match serialize::try_read_message(&mut reader, ReaderOptions::new()) {
+        Ok(Some(message)) => Ok(message),
+        Ok(None) => Err(NetworkError::Closed),
+        Err(e) => Err(match reader.last_error {
+            Some(io_err) => NetworkError::Io(io_err),
+            None => NetworkError::Decode(e),
+        }),
+    }
+}
+
+/// Writes a single packet to the stream
+pub fn write_message<W: Write, A: Allocator>(mut stream: W, message: &Builder<A>) -> Result<(), NetworkError> {
+    // serialize into memory first so I/O errors aren't flattened into capnp errors
+    let mut buf = Vec::new();
+    serialize::write_message(&mut buf, message)?;
+    let result = stream.write_all(buf.as_slice()).and_then(|_| stream.flush());
+    // packets can carry credentials, don't leave copies of them lying around
+    buf.zeroize();
+    Ok(result?)
+}
@@ -1,12 +1,13 @@
-use std::net::TcpStream;
+use std::io::{Read, Write};
 use crate::packet_capnp::event;
-use capnp::message::{TypedReader, Builder, HeapAllocator};
-use capnp::serialize;
+use capnp::message::Builder;
 use crate::utils::systime;
 use crate::network::message::Message;
+use crate::network::server_event::ServerEvent;
+use crate::network::error::{NetworkError, read_message, write_message};
 
 /// Sends a message event
-pub fn write_event_message<S: Into<String>>(mut stream: &TcpStream, msg_str: S, data: S) -> ::capnp::Result<()> {
+pub fn write_event_message<W: Write, S: Into<String>>(stream: W, msg_str: S, data: S) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut ev = message.init_root::<event::Builder>();
@@ -15,70 +16,85 @@ pub fn write_event_message<S: Into<String>>(mut stream: &TcpStream, msg_str: S,
         msgdata.set_message(msg_str.into().as_str());
         msgdata.set_data(data.into().as_str());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
 /// Sends a keepalive request or response
-pub fn write_event_keepalive(mut stream: &TcpStream) -> ::capnp::Result<()> {
+pub fn write_event_keepalive<W: Write>(stream: W) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut ev = message.init_root::<event::Builder>();
         ev.set_disconnect(false);
         ev.set_keepalive(systime().as_secs());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
 /// Sends an error
-pub fn write_event_error<S: Into<String>>(mut stream: &TcpStream, error: S, disconnect: bool) -> ::capnp::Result<()> {
+pub fn write_event_error<W: Write, S: Into<String>>(stream: W, error: S, disconnect: bool) -> Result<(), NetworkError> {
     let mut message = Builder::new_default();
     {
         let mut ev = message.init_root::<event::Builder>();
         ev.set_disconnect(disconnect);
         ev.set_error(error.into().as_str());
     }
-    serialize::write_message(&mut stream, &message)
+    write_message(stream, &message)
 }
 
-/// Reads an event packet, and returns it's data
-/// Returns message, keepalive_time, an error, and a disconnect flag
-pub fn read_event(mut stream: &TcpStream) -> (Option<Message>, Option<u64>, Option<String>, bool) {
-    let mut needs_to_disconnect = false;
-
-    // read the event
-    let message_reader_result = serialize::read_message(&mut stream, ::capnp::message::ReaderOptions::new());
-    if message_reader_result.is_err() { // disconnected
-        return (None, None, None, true);
-    }
-    let message_reader = message_reader_result.unwrap();
+/// Reads an event packet.
+/// Any event with the disconnect flag set is returned as `ServerEvent::Disconnected`,
+/// with the error or message text as the reason.
+pub fn read_event<R: Read>(stream: R) -> Result<ServerEvent, NetworkError> {
+    let message_reader = read_message(stream)?;
     // store the event in a Reader to obtain data out of it
-    let ev_raw = message_reader.get_root::<event::Reader>();
-    if ev_raw.is_err() {
-        return (None, None, None, true);
-    }
-    let ev = ev_raw.unwrap();
-
-    needs_to_disconnect = ev.get_disconnect();
+    let ev = message_reader.get_root::<event::Reader>()?;
+    let disconnect = ev.get_disconnect();
 
     // the event is a Cap'n Proto Union, so go through which type of event it is
-    return match ev.which() {
-        Ok(event::Message(msg)) => {
-            let raw_msg = msg.unwrap();
-            let m = Message {
-                message: raw_msg.get_message().unwrap().to_string(),
-                data: raw_msg.get_data().unwrap().to_string(),
-            };
-            (Some(m), None, None, needs_to_disconnect)
-        }
-        Ok(event::Keepalive(st)) => {
-            (None, Some(st), None, ev.get_disconnect())
+    let event = match ev.which()? {
+        event::Message(msg) => {
+            let raw_msg = msg?;
+            ServerEvent::Message(Message {
+                message: raw_msg.get_message()?.to_string(),
+                data: raw_msg.get_data()?.to_string(),
+            })
         }
-        Ok(event::Error(err)) => {
-            (None, None, Some(err.unwrap().to_string()), needs_to_disconnect)
+        event::Keepalive(st) => ServerEvent::Keepalive(st),
+        event::Error(err) => ServerEvent::Error(err?.to_string()),
+    };
+
+    if !disconnect {
+        return Ok(event);
+    }
+    Ok(ServerEvent::Disconnected(match event {
+        ServerEvent::Message(msg) => msg.message,
+        ServerEvent::Error(reason) | ServerEvent::Disconnected(reason) => reason,
+        ServerEvent::Keepalive(_) => String::new(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip_through_memory() {
+        let mut buf = Vec::new();
+        write_event_message(&mut buf, "hello", "1,2").unwrap();
+        write_event_error(&mut buf, "bye", true).unwrap();
+
+        let mut reader = buf.as_slice();
+        match read_event(&mut reader).unwrap() {
+            ServerEvent::Message(msg) => {
+                assert_eq!(msg.message, "hello");
+                assert_eq!(msg.data, "1,2");
+            }
+            _ => panic!("expected a message"),
         }
-        Err(::capnp::NotInSchema(_)) => {
-            // todo: error?
-            (None, None, Some(String::from("Invalid EntryPoint - no version or login data found!")), needs_to_disconnect)
+        match read_event(&mut reader).unwrap() {
+            ServerEvent::Disconnected(reason) => assert_eq!(reason, "bye"),
+            _ => panic!("expected a disconnect"),
         }
+        assert!(matches!(read_event(&mut reader), Err(NetworkError::Closed)));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,47 @@
+use crate::network::connection::Connection;
+use crate::network::transport::Transport;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use crate::network::event_io::{read_event, write_event_keepalive};
+use crate::network::server_event::ServerEvent;
+use crate::network::error::NetworkError;
+
+/// The write half of a connection, shared between the threads that send packets
+pub type SharedWriter<S = Connection> = Arc<Mutex<S>>;
+
+/// Reads events from `stream` on a background thread and forwards them to `events`.
+/// Keepalives are answered through `writer` on the reader thread, so they never wait on the user.
+/// The thread stops once the connection is closed or `events` is dropped.
+pub fn spawn_event_reader<S, T>(mut stream: S, writer: SharedWriter<S>, events: Sender<T>) -> JoinHandle<()>
+    where S: Transport, T: From<Result<ServerEvent, NetworkError>> + Send + 'static {
+    thread::spawn(move || {
+        loop {
+            let event = read_event(&mut stream);
+            let done = match &event {
+                Ok(ServerEvent::Keepalive(_)) => {
+                    let written = match writer.lock() {
+                        Ok(mut writer) => write_event_keepalive(&mut *writer),
+                        Err(_) => break,
+                    };
+                    // keepalives are handled here, only forward failures
+                    match written {
+                        Ok(()) => continue,
+                        Err(e) => {
+                            let _ = events.send(T::from(Err(e)));
+                            break;
+                        }
+                    }
+                }
+                Ok(ServerEvent::Disconnected(_)) => true,
+                Err(NetworkError::Closed) | Err(NetworkError::Io(_)) => true,
+                _ => false,
+            };
+
+            // stop if the receiving end is gone
+            if events.send(T::from(event)).is_err() || done {
+                break;
+            }
+        }
+    })
+}
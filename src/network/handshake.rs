@@ -0,0 +1,327 @@
+use std::fmt;
+use crate::network::entry_point_io::{write_entry_point_ver, write_entry_auth_start, write_entry_auth_proof, write_entry_signup, write_entry_resume};
+use crate::network::entry_response_io::read_entry_response;
+use crate::network::login_data::LoginData;
+use crate::network::entry_response::EntryResponse;
+use crate::network::error::NetworkError;
+use crate::network::connection::Connection;
+use crate::network::tls::{TlsConfig, FingerprintStore};
+use crate::network::transport::Transport;
+use crate::network::scram::{self, Verifier, MAX_ITERATIONS, MIN_ITERATIONS, NONCE_LEN, SIGNUP_ITERATIONS};
+
+/// The stages of the entry handshake. The version check, login and game session all share one connection:
+/// Connected -> VersionAccepted -> (login attempts) -> LoggedIn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HandshakeState {
+    /// connected, but the version has not been checked yet
+    Connected,
+    /// the server accepted our version, login attempts can be made
+    VersionAccepted,
+    /// logged in, the stream now carries events
+    LoggedIn,
+    /// the handshake failed and the connection can not be used anymore
+    Failed,
+}
+
+#[derive(Debug)]
+pub enum HandshakeError {
+    /// the server and client versions do not match
+    Outdated { server_version: String },
+    /// the server rejected the login attempt, another attempt can be made
+    LoginRejected(String),
+    /// the connection failed or the server sent a broken packet
+    Network(NetworkError),
+    /// the server sent something that does not belong in the current state
+    Protocol(String),
+    /// the challenge-response exchange could not be completed safely,
+    /// for example because the server could not prove it knows the password's verifier
+    Authentication(String),
+    /// the user stopped the login before it finished
+    Cancelled,
+    /// a step was attempted out of order
+    InvalidState { expected: HandshakeState, actual: HandshakeState },
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandshakeError::Outdated { server_version } => write!(f, "Your client is outdated! The server is running {} while you're still on {}! Please make sure to update!", server_version, crate::VERSION),
+            HandshakeError::LoginRejected(reason) => write!(f, "Login Attempt Failed.\n{}", reason),
+            HandshakeError::Network(e) => write!(f, "Lost connection to the server: {}", e),
+            HandshakeError::Protocol(reason) => write!(f, "The server sent an invalid response: {}", reason),
+            HandshakeError::Authentication(reason) => write!(f, "Authentication failed: {}", reason),
+            HandshakeError::Cancelled => write!(f, "The login was cancelled."),
+            HandshakeError::InvalidState { expected, actual } => write!(f, "Handshake step requires state {:?}, but the handshake is in state {:?}", expected, actual),
+        }
+    }
+}
+
+impl From<NetworkError> for HandshakeError {
+    fn from(e: NetworkError) -> Self {
+        HandshakeError::Network(e)
+    }
+}
+
+/// The result of a successful login or resume
+pub struct LoggedIn {
+    pub motd: String,
+    /// presented after reconnecting to continue the session without logging in again
+    pub resume_token: Option<String>,
+}
+
+/// Drives the entry handshake over a single connection
+pub struct Handshake<S: Transport = Connection> {
+    stream: S,
+    state: HandshakeState,
+}
+
+impl Handshake<Connection> {
+    /// Connects to the server, over TLS if `tls` is set
+    pub fn connect(host: &str, port: u16, tls: Option<(&TlsConfig, FingerprintStore)>) -> Result<Self, HandshakeError> {
+        let stream = Connection::connect(host, port, tls)
+            .map_err(|e| HandshakeError::Network(NetworkError::Io(e)))?;
+        Ok(Self::new(stream))
+    }
+}
+
+impl<S: Transport> Handshake<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            state: HandshakeState::Connected,
+        }
+    }
+
+    pub fn state(&self) -> HandshakeState {
+        self.state
+    }
+
+    fn expect_state(&self, expected: HandshakeState) -> Result<(), HandshakeError> {
+        if self.state != expected {
+            return Err(HandshakeError::InvalidState { expected, actual: self.state });
+        }
+        Ok(())
+    }
+
+    /// Marks the handshake as failed if the error can not be recovered from
+    fn fail(&mut self, err: HandshakeError) -> HandshakeError {
+        if !matches!(err, HandshakeError::LoginRejected(_)) {
+            self.state = HandshakeState::Failed;
+        }
+        err
+    }
+
+    /// Sends the client version and waits for the server to accept it
+    pub fn check_version(&mut self, version: &str) -> Result<(), HandshakeError> {
+        self.expect_state(HandshakeState::Connected)?;
+
+        if let Err(e) = write_entry_point_ver(&mut self.stream, version.to_string()) {
+            return Err(self.fail(HandshakeError::Network(e)));
+        }
+
+        let result = match read_entry_response(&mut self.stream) {
+            Ok(EntryResponse::Version { valid: true, .. }) => Ok(()),
+            Ok(EntryResponse::Version { valid: false, version }) => Err(HandshakeError::Outdated { server_version: version }),
+            Ok(EntryResponse::Error(err)) => Err(HandshakeError::Protocol(err)),
+            Ok(EntryResponse::Motd { .. }) | Ok(EntryResponse::Challenge(_)) => Err(HandshakeError::Protocol("expected the server version".to_string())),
+            Err(e) => Err(HandshakeError::Network(e)),
+        };
+
+        match result {
+            Ok(()) => {
+                self.state = HandshakeState::VersionAccepted;
+                Ok(())
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Attempts to log in, or signs up if `login_data.signup` is set.
+    /// The password never leaves the client: logins prove knowledge of it through a challenge-response exchange,
+    /// and signups send a verifier derived from it.
+    /// If the attempt is rejected, the handshake stays in `VersionAccepted` so it can be retried.
+    pub fn login(&mut self, login_data: LoginData) -> Result<LoggedIn, HandshakeError> {
+        self.expect_state(HandshakeState::VersionAccepted)?;
+
+        let result = if login_data.signup {
+            self.signup(&login_data)
+        } else {
+            self.authenticate(&login_data)
+        };
+        self.finish_login(result)
+    }
+
+    /// Attempts to continue a previous session with the resume token it was issued.
+    /// If the server no longer knows the token, the attempt is rejected and a normal login can be made instead.
+    pub fn resume(&mut self, resume_token: &str) -> Result<LoggedIn, HandshakeError> {
+        self.expect_state(HandshakeState::VersionAccepted)?;
+
+        let result = write_entry_resume(&mut self.stream, resume_token)
+            .map_err(HandshakeError::from)
+            .and_then(|_| self.read_login_response());
+        self.finish_login(result)
+    }
+
+    fn signup(&mut self, login_data: &LoginData) -> Result<LoggedIn, HandshakeError> {
+        let verifier = Verifier::new(login_data.passwd.as_str(), SIGNUP_ITERATIONS)
+            .map_err(HandshakeError::Authentication)?;
+        write_entry_signup(&mut self.stream, login_data.email.as_str(), login_data.username.as_str(), &verifier)?;
+        self.read_login_response()
+    }
+
+    fn authenticate(&mut self, login_data: &LoginData) -> Result<LoggedIn, HandshakeError> {
+        let client_nonce = scram::random_bytes(NONCE_LEN).map_err(HandshakeError::Authentication)?;
+        write_entry_auth_start(&mut self.stream, login_data.username.as_str(), client_nonce.as_slice())?;
+
+        let challenge = match read_entry_response(&mut self.stream)? {
+            EntryResponse::Challenge(challenge) => challenge,
+            EntryResponse::Error(err) => return Err(HandshakeError::LoginRejected(err)),
+            _ => return Err(HandshakeError::Protocol("expected an authentication challenge".to_string())),
+        };
+        // the server's nonce has to extend ours, otherwise the proof could be replayed
+        if challenge.nonce.len() <= client_nonce.len() || !challenge.nonce.starts_with(client_nonce.as_slice()) {
+            return Err(HandshakeError::Authentication("the server's nonce does not match ours".to_string()));
+        }
+        if challenge.iterations < MIN_ITERATIONS {
+            return Err(HandshakeError::Authentication(format!("the server asked for only {} iterations", challenge.iterations)));
+        }
+        if challenge.iterations > MAX_ITERATIONS {
+            return Err(HandshakeError::Authentication(format!("the server asked for {} iterations, more than the {} allowed", challenge.iterations, MAX_ITERATIONS)));
+        }
+
+        let proof = scram::client_proof(login_data.username.as_str(), login_data.passwd.as_str(), client_nonce.as_slice(), &challenge);
+        write_entry_auth_proof(&mut self.stream, challenge.nonce.as_slice(), proof.proof.as_slice())?;
+
+        match read_entry_response(&mut self.stream)? {
+            EntryResponse::Motd { motd, resume_token, server_signature: Some(signature) }
+                if scram::constant_time_eq(signature.as_slice(), proof.server_signature.as_slice()) => {
+                Ok(LoggedIn { motd, resume_token })
+            }
+            EntryResponse::Motd { .. } => Err(HandshakeError::Authentication("the server could not prove it knows your password".to_string())),
+            EntryResponse::Error(err) => Err(HandshakeError::LoginRejected(err)),
+            _ => Err(HandshakeError::Protocol("expected a MOTD after logging in".to_string())),
+        }
+    }
+
+    fn read_login_response(&mut self) -> Result<LoggedIn, HandshakeError> {
+        match read_entry_response(&mut self.stream)? {
+            EntryResponse::Motd { motd, resume_token, .. } => Ok(LoggedIn { motd, resume_token }),
+            EntryResponse::Error(err) => Err(HandshakeError::LoginRejected(err)),
+            _ => Err(HandshakeError::Protocol("expected a MOTD after logging in".to_string())),
+        }
+    }
+
+    fn finish_login(&mut self, result: Result<LoggedIn, HandshakeError>) -> Result<LoggedIn, HandshakeError> {
+        match result {
+            Ok(logged_in) => {
+                self.state = HandshakeState::LoggedIn;
+                Ok(logged_in)
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Hands over the connection for the game session once logged in
+    pub fn into_stream(self) -> Result<S, HandshakeError> {
+        self.expect_state(HandshakeState::LoggedIn)?;
+        Ok(self.stream)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+    use crate::network::entry_point::EntryPoint;
+    use crate::network::entry_point_io::read_entry_point;
+    use crate::network::entry_response_io::{write_challenge_entry_response, write_invalid_entry_response, write_ping_entry_response, write_valid_entry_response};
+    use crate::network::scram::{auth_message, verify_proof, AuthChallenge};
+
+    fn login_data(username: &str, passwd: &str) -> LoginData {
+        LoginData { email: String::new(), username: username.to_string(), passwd: passwd.to_string(), signup: false }
+    }
+
+    /// Plays the server side of a version check and one challenge-response login for "alice"
+    fn serve_login(mut stream: UnixStream, verifier: Verifier) {
+        match read_entry_point(&mut stream).unwrap() {
+            EntryPoint::Version(version) => write_ping_entry_response(&mut stream, true, version).unwrap(),
+            _ => panic!("expected a version ping"),
+        }
+
+        let (username, client_nonce) = match read_entry_point(&mut stream).unwrap() {
+            EntryPoint::AuthStart { username, client_nonce } => (username, client_nonce),
+            _ => panic!("expected the start of a login"),
+        };
+        let mut nonce = client_nonce.clone();
+        nonce.extend(scram::random_bytes(NONCE_LEN).unwrap());
+        let challenge = AuthChallenge { salt: verifier.salt.clone(), iterations: verifier.iterations, nonce };
+        write_challenge_entry_response(&mut stream, &challenge).unwrap();
+
+        let proof = match read_entry_point(&mut stream).unwrap() {
+            EntryPoint::AuthProof { proof, .. } => proof,
+            _ => panic!("expected a proof"),
+        };
+        let message = auth_message(username.as_str(), client_nonce.as_slice(), &challenge);
+        match verify_proof(&verifier, message.as_slice(), proof.as_slice()) {
+            Some(signature) if username == "alice" => write_valid_entry_response(&mut stream, "welcome".to_string(), None, Some(signature.as_slice())).unwrap(),
+            _ => write_invalid_entry_response(&mut stream, "wrong username or password").unwrap(),
+        }
+    }
+
+    #[test]
+    fn login_over_a_socket_pair() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let verifier = Verifier::new("hunter2", MIN_ITERATIONS).unwrap();
+        let server = thread::spawn(move || serve_login(server, verifier));
+
+        let mut handshake = Handshake::new(client);
+        handshake.check_version(crate::VERSION).unwrap();
+        let logged_in = handshake.login(login_data("alice", "hunter2")).unwrap();
+        assert_eq!(logged_in.motd, "welcome");
+        assert_eq!(handshake.state(), HandshakeState::LoggedIn);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn rejected_login_can_be_retried() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let verifier = Verifier::new("hunter2", MIN_ITERATIONS).unwrap();
+        let server = thread::spawn(move || serve_login(server, verifier));
+
+        let mut handshake = Handshake::new(client);
+        handshake.check_version(crate::VERSION).unwrap();
+        match handshake.login(login_data("alice", "wrong")) {
+            Err(HandshakeError::LoginRejected(reason)) => assert_eq!(reason, "wrong username or password"),
+            _ => panic!("the login should have been rejected"),
+        }
+        assert_eq!(handshake.state(), HandshakeState::VersionAccepted);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn excessive_iterations_are_refused() {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            match read_entry_point(&mut server).unwrap() {
+                EntryPoint::Version(version) => write_ping_entry_response(&mut server, true, version).unwrap(),
+                _ => panic!("expected a version ping"),
+            }
+            let mut nonce = match read_entry_point(&mut server).unwrap() {
+                EntryPoint::AuthStart { client_nonce, .. } => client_nonce,
+                _ => panic!("expected the start of a login"),
+            };
+            nonce.extend(scram::random_bytes(NONCE_LEN).unwrap());
+            let challenge = AuthChallenge { salt: scram::random_bytes(scram::SALT_LEN).unwrap(), iterations: MAX_ITERATIONS + 1, nonce };
+            write_challenge_entry_response(&mut server, &challenge).unwrap();
+        });
+
+        let mut handshake = Handshake::new(client);
+        handshake.check_version(crate::VERSION).unwrap();
+        match handshake.login(login_data("alice", "hunter2")) {
+            Err(HandshakeError::Authentication(_)) => {}
+            _ => panic!("the challenge should have been refused"),
+        }
+        server.join().unwrap();
+    }
+}
@@ -1,6 +1,15 @@
+use zeroize::Zeroize;
+
 pub struct LoginData {
     pub email: String,
     pub username: String,
     pub passwd: String,
     pub signup: bool
-}
\ No newline at end of file
+}
+
+impl Drop for LoginData {
+    /// Wipes the password from memory once it is no longer needed
+    fn drop(&mut self) {
+        self.passwd.zeroize();
+    }
+}
@@ -0,0 +1,165 @@
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+use rustls::crypto::ring;
+use zeroize::Zeroizing;
+
+/// PBKDF2 rounds used for the verifier derived at signup
+pub const SIGNUP_ITERATIONS: u32 = 100_000;
+/// challenges asking for fewer rounds than this are refused, so a server can't weaken the proof
+pub const MIN_ITERATIONS: u32 = 4096;
+/// challenges asking for more rounds than this are refused, so a server can't stall the client
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 24;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What the server stores instead of the password, sent at signup.
+/// Neither key is enough to log in, the client has to prove it knows the password itself.
+pub struct Verifier {
+    pub salt: Vec<u8>,
+    pub iterations: u32,
+    pub stored_key: Vec<u8>,
+    pub server_key: Vec<u8>,
+}
+
+impl Verifier {
+    /// Derives a verifier for `password` with a fresh random salt
+    pub fn new(password: &str, iterations: u32) -> Result<Self, String> {
+        let salt = random_bytes(SALT_LEN)?;
+        Ok(Self::with_salt(password, salt, iterations))
+    }
+
+    pub fn with_salt(password: &str, salt: Vec<u8>, iterations: u32) -> Self {
+        let salted = salted_password(password, salt.as_slice(), iterations);
+        let client_key = Zeroizing::new(hmac(salted.as_slice(), b"Client Key"));
+        Self {
+            stored_key: Sha256::digest(client_key.as_slice()).to_vec(),
+            server_key: hmac(salted.as_slice(), b"Server Key"),
+            salt,
+            iterations,
+        }
+    }
+}
+
+/// The server's challenge for a login attempt
+pub struct AuthChallenge {
+    pub salt: Vec<u8>,
+    pub iterations: u32,
+    /// the client's nonce followed by the server's
+    pub nonce: Vec<u8>,
+}
+
+/// The client's answer to a challenge
+pub struct ClientProof {
+    pub proof: Vec<u8>,
+    /// what the server has to send back to show it knows the verifier
+    pub server_signature: Vec<u8>,
+}
+
+pub fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut buf = vec![0u8; len];
+    ring::default_provider().secure_random.fill(buf.as_mut_slice())
+        .map_err(|_| "Failed to generate random bytes".to_string())?;
+    Ok(buf)
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <HmacSha256 as KeyInit>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn salted_password(password: &str, salt: &[u8], iterations: u32) -> Zeroizing<[u8; 32]> {
+    let mut salted = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, salted.as_mut_slice());
+    salted
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+/// Compares in constant time, so the comparison doesn't leak how much of a signature matched
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The transcript both sides sign: the username, the client nonce, the salt, the iteration count
+/// and the full nonce, each prefixed with its length as a big endian u32
+pub fn auth_message(username: &str, client_nonce: &[u8], challenge: &AuthChallenge) -> Vec<u8> {
+    let iterations = challenge.iterations.to_be_bytes();
+    let parts: [&[u8]; 5] = [username.as_bytes(), client_nonce, challenge.salt.as_slice(), &iterations, challenge.nonce.as_slice()];
+
+    let mut message = Vec::new();
+    for part in parts.iter() {
+        message.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        message.extend_from_slice(part);
+    }
+    message
+}
+
+/// Proves knowledge of `password` for the challenge, without revealing it
+pub fn client_proof(username: &str, password: &str, client_nonce: &[u8], challenge: &AuthChallenge) -> ClientProof {
+    let salted = salted_password(password, challenge.salt.as_slice(), challenge.iterations);
+    let client_key = Zeroizing::new(hmac(salted.as_slice(), b"Client Key"));
+    let stored_key = Sha256::digest(client_key.as_slice());
+    let server_key = Zeroizing::new(hmac(salted.as_slice(), b"Server Key"));
+
+    let message = auth_message(username, client_nonce, challenge);
+    let client_signature = hmac(stored_key.as_slice(), message.as_slice());
+
+    ClientProof {
+        proof: xor(client_key.as_slice(), client_signature.as_slice()),
+        server_signature: hmac(server_key.as_slice(), message.as_slice()),
+    }
+}
+
+/// Checks a client's proof against a stored verifier.
+/// Returns the server signature to send back if the proof is valid.
+pub fn verify_proof(verifier: &Verifier, auth_message: &[u8], proof: &[u8]) -> Option<Vec<u8>> {
+    let client_signature = hmac(verifier.stored_key.as_slice(), auth_message);
+    let client_key = Zeroizing::new(xor(proof, client_signature.as_slice()));
+    if proof.len() != client_signature.len() || !constant_time_eq(Sha256::digest(client_key.as_slice()).as_slice(), verifier.stored_key.as_slice()) {
+        return None;
+    }
+    Some(hmac(verifier.server_key.as_slice(), auth_message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(verifier: &Verifier, client_nonce: &[u8]) -> AuthChallenge {
+        let mut nonce = client_nonce.to_vec();
+        nonce.extend(random_bytes(NONCE_LEN).unwrap());
+        AuthChallenge { salt: verifier.salt.clone(), iterations: verifier.iterations, nonce }
+    }
+
+    #[test]
+    fn proof_from_the_right_password_verifies() {
+        let verifier = Verifier::new("hunter2", MIN_ITERATIONS).unwrap();
+        let client_nonce = random_bytes(NONCE_LEN).unwrap();
+        let challenge = challenge(&verifier, client_nonce.as_slice());
+
+        let proof = client_proof("alice", "hunter2", client_nonce.as_slice(), &challenge);
+        let message = auth_message("alice", client_nonce.as_slice(), &challenge);
+        let server_signature = verify_proof(&verifier, message.as_slice(), proof.proof.as_slice());
+        assert_eq!(server_signature, Some(proof.server_signature));
+    }
+
+    #[test]
+    fn proof_from_the_wrong_password_is_rejected() {
+        let verifier = Verifier::new("hunter2", MIN_ITERATIONS).unwrap();
+        let client_nonce = random_bytes(NONCE_LEN).unwrap();
+        let challenge = challenge(&verifier, client_nonce.as_slice());
+
+        let proof = client_proof("alice", "hunter3", client_nonce.as_slice(), &challenge);
+        let message = auth_message("alice", client_nonce.as_slice(), &challenge);
+        assert_eq!(verify_proof(&verifier, message.as_slice(), proof.proof.as_slice()), None);
+
+        // a proof for one user doesn't work for another
+        let proof = client_proof("bob", "hunter2", client_nonce.as_slice(), &challenge);
+        assert_eq!(verify_proof(&verifier, message.as_slice(), proof.proof.as_slice()), None);
+    }
+}
@@ -0,0 +1,13 @@
+use crate::network::message::Message;
+
+/// An event sent by the server during the game session
+pub enum ServerEvent {
+    /// output and commands for the client
+    Message(Message),
+    /// a keepalive request, holding the server's time
+    Keepalive(u64),
+    /// an error the session can continue after
+    Error(String),
+    /// the server ended the session, with the reason it gave
+    Disconnected(String),
+}
@@ -0,0 +1,417 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned};
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::pki_types::pem::PemObject;
+use crate::network::transport::Transport;
+
+pub const KNOWN_SERVERS_FILE: &str = "known_servers.toml";
+
+/// How long a TLS read waits on the socket before letting a writer use the connection
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// TLS settings for a saved server
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM file with the CA certificates to trust, instead of the built-in roots
+    pub ca_file: Option<PathBuf>,
+    /// name to verify the certificate against, defaults to the server's address
+    pub server_name: Option<String>,
+    /// accept a certificate that can't be verified (such as a self-signed one) the first time,
+    /// and only accept that same certificate afterwards
+    pub trust_on_first_use: bool,
+}
+
+/// Certificate fingerprints pinned by trust-on-first-use, keyed by server address
+#[derive(Serialize, Deserialize, Default)]
+struct KnownServers {
+    #[serde(default)]
+    fingerprints: BTreeMap<String, String>,
+}
+
+/// The file backed fingerprint store for trust-on-first-use.
+/// Clones share what was pinned through them, see `newly_pinned`.
+#[derive(Clone, Debug)]
+pub struct FingerprintStore {
+    path: PathBuf,
+    newly_pinned: Arc<Mutex<Option<String>>>,
+}
+
+impl FingerprintStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into(), newly_pinned: Arc::new(Mutex::new(None)) }
+    }
+
+    /// The fingerprint last pinned through this store or one of its clones, so a caller that handed
+    /// a clone to `TlsStream::connect` can tell the user a certificate was trusted on first use
+    pub fn newly_pinned(&self) -> Option<String> {
+        self.newly_pinned.lock().ok().and_then(|pinned| pinned.clone())
+    }
+
+    fn load(&self) -> Result<KnownServers, String> {
+        if !self.path.exists() {
+            return Ok(KnownServers::default());
+        }
+        let raw = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+        toml::from_str(raw.as_str())
+            .map_err(|e| format!("Failed to parse {}: {}", self.path.display(), e))
+    }
+
+    pub fn get(&self, server: &str) -> Result<Option<String>, String> {
+        Ok(self.load()?.fingerprints.get(server).cloned())
+    }
+
+    pub fn pin(&self, server: &str, fingerprint: &str) -> Result<(), String> {
+        let mut known = self.load()?;
+        known.fingerprints.insert(server.to_string(), fingerprint.to_string());
+        let raw = toml::to_string(&known)
+            .map_err(|e| format!("Failed to serialize the known servers: {}", e))?;
+        fs::write(&self.path, raw)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        if let Ok(mut pinned) = self.newly_pinned.lock() {
+            *pinned = Some(fingerprint.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 fingerprint of a certificate, as colon separated hex
+pub fn fingerprint(cert: &[u8]) -> String {
+    Sha256::digest(cert).iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// Verifies certificates against the trusted roots, falling back to the pinned fingerprint
+/// (or pinning it, the first time) when trust-on-first-use is enabled
+#[derive(Debug)]
+struct PinningVerifier {
+    webpki: Arc<WebPkiServerVerifier>,
+    trust_on_first_use: bool,
+    store: FingerprintStore,
+    /// the key the fingerprint is stored under
+    server: String,
+    lock: Mutex<()>,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, intermediates: &[CertificateDer<'_>], server_name: &ServerName<'_>, ocsp_response: &[u8], now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        let webpki_err = match self.webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+            Ok(verified) => return Ok(verified),
+            Err(e) => e,
+        };
+        if !self.trust_on_first_use {
+            return Err(webpki_err);
+        }
+
+        let _guard = self.lock.lock().map_err(|_| rustls::Error::General("fingerprint store poisoned".to_string()))?;
+        let presented = fingerprint(end_entity.as_ref());
+        match self.store.get(self.server.as_str()).map_err(rustls::Error::General)? {
+            Some(pinned) if pinned == presented => Ok(ServerCertVerified::assertion()),
+            Some(pinned) => Err(rustls::Error::General(format!(
+                "The certificate for {} has changed since it was first trusted! Expected {} but got {}. \
+                If this is expected, remove the server from {}.",
+                self.server, pinned, presented, self.store.path.display()))),
+            None => {
+                self.store.pin(self.server.as_str(), presented.as_str()).map_err(rustls::Error::General)?;
+                Ok(ServerCertVerified::assertion())
+            }
+        }
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.webpki.supported_verify_schemes()
+    }
+}
+
+fn invalid_input<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e.to_string())
+}
+
+fn client_config(config: &TlsConfig, server: &str, store: FingerprintStore) -> io::Result<ClientConfig> {
+    let provider = Arc::new(ring::default_provider());
+
+    let mut roots = RootCertStore::empty();
+    match &config.ca_file {
+        Some(ca_file) => {
+            for cert in CertificateDer::pem_file_iter(ca_file).map_err(|e| invalid_input(format!("Failed to read {}: {}", ca_file.display(), e)))? {
+                roots.add(cert.map_err(invalid_input)?).map_err(invalid_input)?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+
+    let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(invalid_input)?;
+    let verifier = PinningVerifier {
+        webpki,
+        trust_on_first_use: config.trust_on_first_use,
+        store,
+        server: server.to_string(),
+        lock: Mutex::new(()),
+    };
+
+    Ok(ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_input)?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth())
+}
+
+/// A TLS stream that can be shared between a reading and a writing thread.
+/// Reads poll the socket with a short timeout, so a blocked reader never holds the
+/// connection for longer than that and writes can get through.
+#[derive(Clone)]
+pub struct TlsStream {
+    inner: Arc<Mutex<StreamOwned<ClientConnection, TcpStream>>>,
+}
+
+impl TlsStream {
+    /// Runs the TLS handshake over `stream`.
+    /// `host` is the address the server was reached at, `port` is used to key the pinned fingerprint.
+    pub fn connect(stream: TcpStream, host: &str, port: u16, config: &TlsConfig, store: FingerprintStore) -> io::Result<Self> {
+        let name = config.server_name.clone().unwrap_or_else(|| host.to_string());
+        let server_name = ServerName::try_from(name).map_err(invalid_input)?;
+        let client_config = client_config(config, format!("{}:{}", host, port).as_str(), store)?;
+
+        let mut conn = ClientConnection::new(Arc::new(client_config), server_name)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut sock = stream;
+        // finish the handshake up front, so certificate errors show up when connecting
+        while conn.is_handshaking() {
+            conn.complete_io(&mut sock)?;
+        }
+        sock.set_read_timeout(Some(READ_POLL_INTERVAL))?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(StreamOwned::new(conn, sock))),
+        })
+    }
+}
+
+impl Transport for TlsStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(self.clone())
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().map_err(|_| io::Error::other("TLS stream poisoned"))?;
+        inner.conn.send_close_notify();
+        let _ = inner.flush();
+        inner.sock.shutdown(std::net::Shutdown::Both)
+    }
+}
+
+/// Reading has to lock the connection, as rustls keeps the state of both directions in it.
+/// So that a reader waiting on the server doesn't hold up writes, the socket has a read timeout of
+/// `READ_POLL_INTERVAL` and the read is retried until data arrives: a writer waits at most that long
+/// for the lock, and an idle reader wakes up that often. It is not a busy loop, every attempt blocks
+/// in the socket until the timeout.
+impl Read for &TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut inner = self.inner.lock().map_err(|_| io::Error::other("TLS stream poisoned"))?;
+            match inner.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                    // nothing yet, release the lock and let a waiting writer take it before locking again,
+                    // as the mutex isn't fair
+                    drop(inner);
+                    std::thread::yield_now();
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for &TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.lock().map_err(|_| io::Error::other("TLS stream poisoned"))?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.lock().map_err(|_| io::Error::other("TLS stream poisoned"))?.flush()
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedKey, IsCa, Issuer, KeyPair};
+    use rustls::{ServerConfig, ServerConnection};
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    /// A fresh directory for the files a test writes
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mini_mmo_client_tls_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn self_signed() -> CertifiedKey<KeyPair> {
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap()
+    }
+
+    fn key_der(key: &KeyPair) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()))
+    }
+
+    /// Stands in for a TLS server: accepts one connection on `listener` and echoes a single 4 byte ping.
+    /// The listener is handed back so the next server can use the same port.
+    fn spawn_server(listener: TcpListener, chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> JoinHandle<TcpListener> {
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .unwrap();
+
+        thread::spawn(move || {
+            let (sock, _) = listener.accept().unwrap();
+            let conn = ServerConnection::new(Arc::new(config)).unwrap();
+            let mut stream = StreamOwned::new(conn, sock);
+            let mut ping = [0u8; 4];
+            // the client may reject the certificate, that's not the server's problem
+            if stream.read_exact(&mut ping).is_ok() {
+                let _ = stream.write_all(&ping);
+                let _ = stream.flush();
+            }
+            listener
+        })
+    }
+
+    fn listen() -> (TcpListener, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    fn connect(port: u16, config: &TlsConfig, store: FingerprintStore) -> io::Result<TlsStream> {
+        let sock = TcpStream::connect(("127.0.0.1", port))?;
+        TlsStream::connect(sock, "localhost", port, config, store)
+    }
+
+    fn ping(stream: &TlsStream) {
+        let mut stream = stream;
+        stream.write_all(b"ping").unwrap();
+        stream.flush().unwrap();
+        let mut pong = [0u8; 4];
+        stream.read_exact(&mut pong).unwrap();
+        assert_eq!(&pong, b"ping");
+    }
+
+    #[test]
+    fn self_signed_cert_is_rejected_without_tofu() {
+        let dir = temp_dir("no_tofu");
+        let cert = self_signed();
+        let (listener, port) = listen();
+        let server = spawn_server(listener, vec![cert.cert.der().clone()], key_der(&cert.signing_key));
+
+        let result = connect(port, &TlsConfig::default(), FingerprintStore::new(dir.join(KNOWN_SERVERS_FILE)));
+        assert!(result.is_err());
+        assert!(!dir.join(KNOWN_SERVERS_FILE).exists());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn tofu_pins_the_first_cert_and_rejects_a_changed_one() {
+        let dir = temp_dir("tofu");
+        let store = || FingerprintStore::new(dir.join(KNOWN_SERVERS_FILE));
+        let config = TlsConfig { trust_on_first_use: true, ..TlsConfig::default() };
+        let cert = self_signed();
+        let (listener, port) = listen();
+
+        // first use, the certificate gets pinned
+        let server = spawn_server(listener, vec![cert.cert.der().clone()], key_der(&cert.signing_key));
+        let first = store();
+        ping(&connect(port, &config, first.clone()).unwrap());
+        let listener = server.join().unwrap();
+        assert_eq!(first.newly_pinned(), Some(fingerprint(cert.cert.der().as_ref())));
+        let pinned = store().get(format!("localhost:{}", port).as_str()).unwrap();
+        assert_eq!(pinned, Some(fingerprint(cert.cert.der().as_ref())));
+
+        // the same certificate is accepted again, without pinning it anew
+        let server = spawn_server(listener, vec![cert.cert.der().clone()], key_der(&cert.signing_key));
+        let again = store();
+        ping(&connect(port, &config, again.clone()).unwrap());
+        assert_eq!(again.newly_pinned(), None);
+        let listener = server.join().unwrap();
+
+        // a different certificate for the same server is refused, and the pin is left alone
+        let other = self_signed();
+        let server = spawn_server(listener, vec![other.cert.der().clone()], key_der(&other.signing_key));
+        let err = connect(port, &config, store()).err().unwrap();
+        assert!(err.to_string().contains("has changed"), "unexpected error: {}", err);
+        server.join().unwrap();
+        assert_eq!(store().get(format!("localhost:{}", port).as_str()).unwrap(), pinned);
+    }
+    #[test]
+    fn ca_file_verifies_the_server_cert() {
+        let dir = temp_dir("ca_file");
+
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+        let ca = Issuer::new(ca_params, ca_key);
+
+        let leaf_key = KeyPair::generate().unwrap();
+        let leaf = CertificateParams::new(vec!["localhost".to_string()]).unwrap()
+            .signed_by(&leaf_key, &ca)
+            .unwrap();
+
+        let ca_file = dir.join("ca.pem");
+        fs::write(&ca_file, ca_cert.pem()).unwrap();
+        let config = TlsConfig { ca_file: Some(ca_file), ..TlsConfig::default() };
+
+        let (listener, port) = listen();
+        let server = spawn_server(listener, vec![leaf.der().clone(), ca_cert.der().clone()], key_der(&leaf_key));
+        ping(&connect(port, &config, FingerprintStore::new(dir.join(KNOWN_SERVERS_FILE))).unwrap());
+        server.join().unwrap();
+        // verified through the CA, nothing needed pinning
+        assert!(!dir.join(KNOWN_SERVERS_FILE).exists());
+    }
+}
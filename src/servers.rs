@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use better_term::style::{Color, Style};
+use crate::utils::data_dir;
+use crate::{clear_term, get_input};
+use crate::command::policy::{Bounds, Policy};
+use crate::network::tls::{TlsConfig, FingerprintStore, KNOWN_SERVERS_FILE};
+
+pub const SERVERS_FILE: &str = "servers.toml";
+
+/// How many commands a message may run on servers without their own policy
+pub const DEFAULT_COMMANDS_PER_MESSAGE: usize = 50;
+
+/// The policy for servers that don't set one: resizing the terminal has to be allowed explicitly
+pub fn default_policy() -> Policy {
+    Policy {
+        deny: vec!["size".to_string()],
+        max_per_message: Some(DEFAULT_COMMANDS_PER_MESSAGE),
+        ..Policy::default()
+    }
+}
+
+fn is_default_policy(policy: &Policy) -> bool {
+    *policy == default_policy()
+}
+
+/// A `[server.commands]` table as it is written, where every field that is left out keeps its default
+#[derive(Serialize, Deserialize, Default)]
+struct PolicyTable {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    allow: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deny: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    bounds: BTreeMap<String, Bounds>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_per_message: Option<CommandLimit>,
+}
+
+/// A `max_per_message` as it is written: a number, or "unlimited" to lift the default limit
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CommandLimit {
+    Count(usize),
+    Unlimited(Unlimited),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Unlimited {
+    Unlimited,
+}
+
+fn deserialize_policy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Policy, D::Error> {
+    let table = PolicyTable::deserialize(deserializer)?;
+    let default = default_policy();
+    let mut bounds = default.bounds;
+    bounds.extend(table.bounds);
+    Ok(Policy {
+        allow: table.allow.or(default.allow),
+        deny: table.deny.unwrap_or(default.deny),
+        bounds,
+        max_per_message: match table.max_per_message {
+            Some(CommandLimit::Count(max)) => Some(max),
+            Some(CommandLimit::Unlimited(_)) => None,
+            None => default.max_per_message,
+        },
+    })
+}
+
+/// Writes only what differs from the default policy, so it reads back the same
+fn serialize_policy<S: Serializer>(policy: &Policy, serializer: S) -> Result<S::Ok, S::Error> {
+    let default = default_policy();
+    PolicyTable {
+        allow: policy.allow.clone().filter(|allow| Some(allow) != default.allow.as_ref()),
+        deny: Some(policy.deny.clone()).filter(|deny| *deny != default.deny),
+        bounds: policy.bounds.iter()
+            .filter(|(name, bounds)| default.bounds.get(*name) != Some(bounds))
+            .map(|(name, bounds)| (name.clone(), *bounds))
+            .collect(),
+        max_per_message: match policy.max_per_message {
+            max if max == default.max_per_message => None,
+            Some(max) => Some(CommandLimit::Count(max)),
+            None => Some(CommandLimit::Unlimited(Unlimited::Unlimited)),
+        },
+    }.serialize(serializer)
+}
+
+/// A single saved server
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerEntry {
+    pub name: String,
+    pub ip: String,
+    pub port: u16,
+    /// connect over TLS with these settings, or in plaintext if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// what the server may run through `Message.data`. A `[server.commands]` table is laid over
+    /// the default policy, so `size` stays denied unless the table sets its own `deny`,
+    /// and the default limit applies unless it sets `max_per_message`, to "unlimited" to lift it.
+    #[serde(default = "default_policy", skip_serializing_if = "is_default_policy",
+        serialize_with = "serialize_policy", deserialize_with = "deserialize_policy")]
+    pub commands: Policy,
+}
+
+impl ServerEntry {
+    pub fn new<S: Into<String>>(name: S, ip: S, port: u16) -> Self {
+        Self {
+            name: name.into(),
+            ip: ip.into(),
+            port,
+            tls: None,
+            commands: default_policy(),
+        }
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+
+    /// The TLS settings and fingerprint store to connect with, if this server uses TLS
+    pub fn tls(&self) -> Result<Option<(&TlsConfig, FingerprintStore)>, String> {
+        match &self.tls {
+            Some(tls) => Ok(Some((tls, FingerprintStore::new(data_dir()?.join(KNOWN_SERVERS_FILE))))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The list of saved servers, stored as TOML in the client data directory
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ServerList {
+    /// name of the server to use when the user doesn't pick one
+    pub default: Option<String>,
+    #[serde(default, rename = "server")]
+    pub servers: Vec<ServerEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ServerList {
+    /// Loads the server list from the client data directory, creating it if it doesn't exist
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(data_dir()?.join(SERVERS_FILE))
+    }
+
+    /// Loads the server list from the given file, creating it if it doesn't exist
+    pub fn load_from<P: Into<PathBuf>>(path: P) -> Result<Self, String> {
+        let path = path.into();
+
+        if !path.exists() {
+            let list = Self {
+                default: Some("local".to_string()),
+                servers: vec![ServerEntry::new("local", "localhost", 2277)],
+                path,
+            };
+            list.save()?;
+            return Ok(list);
+        }
+
+        let raw = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut list: Self = toml::from_str(raw.as_str())
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        list.path = path;
+        Ok(list)
+    }
+
+    /// Writes the server list back to the file it was loaded from
+    pub fn save(&self) -> Result<(), String> {
+        let raw = toml::to_string(self)
+            .map_err(|e| format!("Failed to serialize the server list: {}", e))?;
+        fs::write(&self.path, raw)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ServerEntry> {
+        self.servers.iter().find(|s| s.name == name)
+    }
+
+    pub fn get_default(&self) -> Option<&ServerEntry> {
+        self.default.as_ref().and_then(|name| self.get(name))
+    }
+
+    pub fn add(&mut self, entry: ServerEntry) -> Result<(), String> {
+        if self.get(entry.name.as_str()).is_some() {
+            return Err(format!("A server named '{}' already exists!", entry.name));
+        }
+        self.servers.push(entry);
+        Ok(())
+    }
+
+    /// Replaces the server named `name` with `entry`, keeping it the default if it was
+    pub fn edit(&mut self, name: &str, entry: ServerEntry) -> Result<(), String> {
+        if entry.name != name && self.get(entry.name.as_str()).is_some() {
+            return Err(format!("A server named '{}' already exists!", entry.name));
+        }
+        let existing = self.servers.iter_mut().find(|s| s.name == name)
+            .ok_or_else(|| format!("The server '{}' does not exist!", name))?;
+        if self.default.as_deref() == Some(name) {
+            self.default = Some(entry.name.clone());
+        }
+        *existing = entry;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<ServerEntry, String> {
+        let index = self.servers.iter().position(|s| s.name == name)
+            .ok_or_else(|| format!("The server '{}' does not exist!", name))?;
+        if self.default.as_deref() == Some(name) {
+            self.default = None;
+        }
+        Ok(self.servers.remove(index))
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<(), String> {
+        if self.get(name).is_none() {
+            return Err(format!("The server '{}' does not exist!", name));
+        }
+        self.default = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Resolves a picker argument, which can be either a server's number in the list or its name
+    fn resolve(&self, arg: &str) -> Option<String> {
+        if let Ok(index) = arg.parse::<usize>() {
+            return self.servers.get(index.wrapping_sub(1)).map(|s| s.name.clone());
+        }
+        self.get(arg).map(|s| s.name.clone())
+    }
+
+    /// A line for each saved server, numbered like the picker numbers them
+    pub fn describe(&self) -> Vec<String> {
+        if self.servers.is_empty() {
+            return vec!["(none, use 'add' to create one)".to_string()];
+        }
+        self.servers.iter().enumerate().map(|(i, server)| {
+            let default = if self.default.as_deref() == Some(server.name.as_str()) { " (default)" } else { "" };
+            let tls = if server.tls.is_some() { " (TLS)" } else { "" };
+            format!("[{}] {} - {}{}{}", i + 1, server.name, server.address(), tls, default)
+        }).collect()
+    }
+
+    fn print(&self) {
+        println!("Saved servers:");
+        for line in self.describe() {
+            println!("  {}", line);
+        }
+        println!("\nType a number or name to connect, or press enter for the default server.");
+        println!("Commands: add, edit <server>, remove <server>, default <server>, quit");
+    }
+
+    /// Interactive server picker, returns None if the user chose to quit
+    pub fn select(&mut self) -> Option<ServerEntry> {
+        loop {
+            // the list is drawn fresh after every command
+            clear_term();
+            self.print();
+            let input = get_input("> ");
+            let mut split = input.trim().splitn(2, ' ');
+            let cmd = split.next().unwrap_or("");
+            let arg = split.next().unwrap_or("").trim();
+
+            let result = match cmd {
+                "" => match self.get_default() {
+                    Some(server) => return Some(server.clone()),
+                    None => Err("There is no default server set!".to_string()),
+                },
+                "quit" | "exit" => return None,
+                "add" => prompt_entry(None).and_then(|entry| self.add(entry)),
+                "edit" => match self.resolve(arg) {
+                    Some(name) => {
+                        let current = self.get(name.as_str()).cloned();
+                        prompt_entry(current.as_ref()).and_then(|entry| self.edit(name.as_str(), entry))
+                    }
+                    None => Err(format!("The server '{}' does not exist!", arg)),
+                },
+                "remove" => match self.resolve(arg) {
+                    Some(name) => self.remove(name.as_str()).map(|_| ()),
+                    None => Err(format!("The server '{}' does not exist!", arg)),
+                },
+                "default" => match self.resolve(arg) {
+                    Some(name) => self.set_default(name.as_str()),
+                    None => Err(format!("The server '{}' does not exist!", arg)),
+                },
+                _ => match self.resolve(input.trim()) {
+                    Some(name) => return self.get(name.as_str()).cloned(),
+                    None => Err(format!("Unknown server or command '{}'", input.trim())),
+                },
+            };
+
+            if let Err(e) = result.and_then(|_| self.save()) {
+                println!("{}{}{}", Color::Red, e, Style::default().overwrite());
+            }
+        }
+    }
+}
+
+/// Prompts for the fields of a server entry, using `current` for any fields left blank
+fn prompt_entry(current: Option<&ServerEntry>) -> Result<ServerEntry, String> {
+    let hint = |value: String| if current.is_some() { format!(" [{}]", value) } else { String::new() };
+
+    let name = get_input(format!("Name{}: ", hint(current.map(|c| c.name.clone()).unwrap_or_default())));
+    let ip = get_input(format!("Address{}: ", hint(current.map(|c| c.ip.clone()).unwrap_or_default())));
+    let port = get_input(format!("Port{}: ", hint(current.map(|c| c.port.to_string()).unwrap_or_default())));
+    let tls = get_input(format!("Use TLS? (y/n){}: ", hint(if current.and_then(|c| c.tls.as_ref()).is_some() { "y" } else { "n" }.to_string())));
+
+    let name = match (name.trim(), current) {
+        ("", Some(c)) => c.name.clone(),
+        ("", None) => return Err("A server needs a name!".to_string()),
+        (n, _) => n.to_string(),
+    };
+    let ip = match (ip.trim(), current) {
+        ("", Some(c)) => c.ip.clone(),
+        ("", None) => return Err("A server needs an address!".to_string()),
+        (i, _) => i.to_string(),
+    };
+    let port = match (port.trim(), current) {
+        ("", Some(c)) => c.port,
+        ("", None) => 2277,
+        (p, _) => p.parse::<u16>().map_err(|_| format!("'{}' is not a valid port!", p))?,
+    };
+
+    let tls = match (tls.trim().to_lowercase().as_str(), current) {
+        ("", Some(c)) => c.tls.clone(),
+        ("", None) | ("n", _) | ("no", _) => None,
+        ("y", _) | ("yes", _) => Some(prompt_tls(current.and_then(|c| c.tls.as_ref()))),
+        (t, _) => return Err(format!("'{}' is not y or n!", t)),
+    };
+
+    // the command policy is only edited in the servers file
+    let commands = current.map(|c| c.commands.clone()).unwrap_or_else(default_policy);
+    Ok(ServerEntry { name, ip, port, tls, commands })
+}
+
+/// Asks for the TLS settings of a server, blank answers keep the `current` settings
+fn prompt_tls(current: Option<&TlsConfig>) -> TlsConfig {
+    let current = current.cloned().unwrap_or_default();
+
+    let ca_hint = current.ca_file.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "built-in roots".to_string());
+    let ca_file = get_input(format!("CA file [{}] (\"-\" for built-in roots): ", ca_hint));
+    let tofu_hint = if current.trust_on_first_use { "y" } else { "n" };
+    let tofu = get_input(format!("Trust a self-signed certificate on first use? (y/n) [{}]: ", tofu_hint));
+
+    let ca_file = match ca_file.trim() {
+        "" => current.ca_file,
+        "-" => None,
+        path => Some(path.into()),
+    };
+    let trust_on_first_use = match tofu.trim().to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => current.trust_on_first_use,
+    };
+
+    TlsConfig { ca_file, server_name: current.server_name, trust_on_first_use }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The path of a servers file that doesn't exist yet, in a fresh directory
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mini_mmo_client_servers_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(SERVERS_FILE)
+    }
+
+    #[test]
+    fn a_missing_file_starts_with_the_local_server() {
+        let path = temp_file("missing");
+        let list = ServerList::load_from(&path).unwrap();
+        assert!(path.exists());
+        assert_eq!(list.servers, vec![ServerEntry::new("local", "localhost", 2277)]);
+        assert_eq!(list.get_default().map(|s| s.name.as_str()), Some("local"));
+    }
+
+    #[test]
+    fn servers_are_added_edited_and_removed() {
+        let path = temp_file("edit");
+        let mut list = ServerList::load_from(&path).unwrap();
+
+        list.add(ServerEntry::new("main", "example.com", 2277)).unwrap();
+        assert_eq!(list.add(ServerEntry::new("main", "other.com", 1)).unwrap_err(), "A server named 'main' already exists!");
+
+        // renaming the default server keeps it the default
+        list.edit("local", ServerEntry::new("home", "127.0.0.1", 2300)).unwrap();
+        assert_eq!(list.default.as_deref(), Some("home"));
+        assert_eq!(list.edit("home", ServerEntry::new("main", "127.0.0.1", 2300)).unwrap_err(), "A server named 'main' already exists!");
+        assert_eq!(list.edit("gone", ServerEntry::new("gone", "127.0.0.1", 1)).unwrap_err(), "The server 'gone' does not exist!");
+
+        list.save().unwrap();
+        let mut list = ServerList::load_from(&path).unwrap();
+        assert_eq!(list.get("home").unwrap().address(), "127.0.0.1:2300");
+        assert_eq!(list.get("main").unwrap().address(), "example.com:2277");
+
+        // removing the default server leaves no default
+        assert_eq!(list.remove("home").unwrap().name, "home");
+        assert_eq!(list.default, None);
+        assert!(list.remove("home").is_err());
+        assert_eq!(list.servers.len(), 1);
+    }
+
+    #[test]
+    fn the_default_has_to_exist() {
+        let mut list = ServerList::load_from(temp_file("default")).unwrap();
+        list.add(ServerEntry::new("main", "example.com", 2277)).unwrap();
+        list.set_default("main").unwrap();
+        assert_eq!(list.get_default().unwrap().name, "main");
+        assert_eq!(list.set_default("gone").unwrap_err(), "The server 'gone' does not exist!");
+        assert_eq!(list.default.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn picker_arguments_are_numbers_or_names() {
+        let mut list = ServerList::load_from(temp_file("resolve")).unwrap();
+        list.add(ServerEntry::new("main", "example.com", 2277)).unwrap();
+        assert_eq!(list.resolve("1").as_deref(), Some("local"));
+        assert_eq!(list.resolve("2").as_deref(), Some("main"));
+        assert_eq!(list.resolve("main").as_deref(), Some("main"));
+        assert_eq!(list.resolve("0"), None);
+        assert_eq!(list.resolve("3"), None);
+        assert_eq!(list.resolve("other"), None);
+    }
+
+    #[test]
+    fn command_tables_are_laid_over_the_default_policy() {
+        let path = temp_file("policy");
+        fs::write(&path, r#"
+            [[server]]
+            name = "bounded"
+            ip = "localhost"
+            port = 2277
+            [server.commands]
+            bounds = { "cursor.x" = { max = 200 } }
+
+            [[server]]
+            name = "trusted"
+            ip = "localhost"
+            port = 2278
+            [server.commands]
+            deny = []
+            max_per_message = 5
+
+            [[server]]
+            name = "unlimited"
+            ip = "localhost"
+            port = 2279
+            [server.commands]
+            max_per_message = "unlimited"
+        "#).unwrap();
+
+        let list = ServerList::load_from(&path).unwrap();
+        let bounded = &list.get("bounded").unwrap().commands;
+        assert_eq!(bounded.deny, default_policy().deny);
+        assert_eq!(bounded.max_per_message, Some(DEFAULT_COMMANDS_PER_MESSAGE));
+        assert_eq!(bounded.bounds["cursor.x"], Bounds { min: None, max: Some(200) });
+        let trusted = &list.get("trusted").unwrap().commands;
+        assert!(trusted.deny.is_empty());
+        assert_eq!(trusted.max_per_message, Some(5));
+        assert_eq!(list.get("unlimited").unwrap().commands.max_per_message, None);
+
+        // both read back the same after saving
+        list.save().unwrap();
+        let saved = ServerList::load_from(&path).unwrap();
+        assert_eq!(saved.servers, list.servers);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::io::{self, Write, stdin};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+use crate::network::server_event::ServerEvent;
+use crate::network::error::NetworkError;
+use crossterm::event::Event;
+use crossterm::tty::IsTty;
+use crate::{read_console, read_password};
+
+/// Everything the session loop reacts to. Server events and user input arrive independently,
+/// so the server can push messages at any time and the user can send several lines in a row.
+pub enum SessionEvent {
+    /// an event (or read failure) from the server
+    Server(Result<ServerEvent, NetworkError>),
+    /// a line the user entered
+    Input(String),
+    /// the user closed the input stream
+    InputClosed,
+    /// the line being read was called off, see `InputReader::cancel`
+    InputCancelled,
+    /// the process was asked to stop, by Ctrl+C or SIGTERM
+    Interrupted,
+    /// a key press or resize, in the full-screen interface
+    Terminal(Event),
+}
+
+impl From<Result<ServerEvent, NetworkError>> for SessionEvent {
+    fn from(event: Result<ServerEvent, NetworkError>) -> Self {
+        SessionEvent::Server(event)
+    }
+}
+
+/// Why a game session ended
+pub enum SessionEnd {
+    /// the user quit, after logging out
+    Quit,
+    /// the user asked for a new connection, resuming the session over it
+    Reconnect,
+    /// the server ended the session, with the reason it gave
+    Kicked(String),
+    /// the connection dropped, the session can be resumed by reconnecting
+    ConnectionLost(NetworkError),
+}
+
+/// How often a reader waiting on a line checks whether it was called off
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+enum Request {
+    Line,
+    /// a line read without echo, after showing the prompt
+    Secret(String),
+    /// stops waiting on a line, throwing away whatever was typed
+    Cancel,
+}
+
+/// Reads lines from the terminal on a background thread and forwards them to the session's events,
+/// for the plain line interface. The thread is the only one reading the terminal, passwords included,
+/// so a line that is already being read can't end up as the answer to a secret prompt.
+pub struct InputReader {
+    requests: Sender<Request>,
+    pending: bool,
+    /// whether the input is typed, rather than piped in
+    terminal: bool,
+}
+
+impl InputReader {
+    pub fn spawn(events: Sender<SessionEvent>) -> Self {
+        let (requests, request_rx) = channel::<Request>();
+        let terminal = stdin().is_tty();
+
+        thread::spawn(move || {
+            // requests that came in while a line was being waited on, handled after it
+            let mut queued = VecDeque::new();
+            while let Some(request) = queued.pop_front().or_else(|| request_rx.recv().ok()) {
+                let event = match request {
+                    Request::Line if !terminal || wait_for_line(&request_rx, &mut queued) => {
+                        let line = read_console();
+                        // read_line returns an empty string (without a newline) at EOF
+                        if line.is_empty() {
+                            let _ = events.send(SessionEvent::InputClosed);
+                            break;
+                        }
+                        SessionEvent::Input(line.trim_end_matches(['\r', '\n']).to_string())
+                    }
+                    Request::Line | Request::Cancel => {
+                        discard_typed();
+                        SessionEvent::InputCancelled
+                    }
+                    Request::Secret(prompt) => match read_password(prompt) {
+                        Some(secret) => SessionEvent::Input(secret),
+                        None => SessionEvent::Interrupted,
+                    },
+                };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests,
+            pending: false,
+            terminal,
+        }
+    }
+
+    /// Asks the reader thread for the next line, unless it is already reading one
+    pub fn request_line(&mut self) {
+        if !self.pending {
+            self.pending = self.requests.send(Request::Line).is_ok();
+        }
+    }
+
+    /// Asks the reader thread for a line read without echo, after showing `prompt`.
+    /// Piped input isn't echoed anyway, so it is read like any other line.
+    pub fn request_secret(&mut self, prompt: &str) {
+        if !self.terminal {
+            print!("{}", prompt);
+            let _ = io::stdout().flush();
+            return self.request_line();
+        }
+        self.pending = self.requests.send(Request::Secret(prompt.to_string())).is_ok();
+    }
+
+    /// Calls off the line being read from the terminal, if any, throwing away what was typed.
+    /// Returns whether the reader is going to answer with `SessionEvent::InputCancelled`,
+    /// which comes after any line it read before. Piped input can't be called off.
+    pub fn cancel(&mut self) -> bool {
+        if !self.terminal {
+            return false;
+        }
+        self.pending = false;
+        self.requests.send(Request::Cancel).is_ok()
+    }
+
+    /// Called when a `SessionEvent::Input` is received, so the next line can be requested
+    pub fn line_received(&mut self) {
+        self.pending = false;
+    }
+}
+
+/// Waits until a whole line can be read, returning false if the read was called off meanwhile.
+/// Other requests that come in are added to `queued`, for after the line.
+/// A blocked read can't be interrupted, so the input is polled until a line is there.
+#[cfg(unix)]
+fn wait_for_line(requests: &Receiver<Request>, queued: &mut VecDeque<Request>) -> bool {
+    loop {
+        match requests.try_recv() {
+            Ok(Request::Cancel) | Err(TryRecvError::Disconnected) => return false,
+            Ok(request) => queued.push_back(request),
+            Err(TryRecvError::Empty) => {}
+        }
+        let mut stdin = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+        // ready once a line was entered, or at the end of the input. A terminal hands out
+        // a line per read, so nothing is left waiting in the buffer of `stdin()` meanwhile
+        // SAFETY: `stdin` is a valid pollfd that outlives the call, and the count of 1 matches it
+        if unsafe { libc::poll(&mut stdin, 1, CANCEL_POLL.as_millis() as libc::c_int) } > 0 {
+            return true;
+        }
+    }
+}
+
+/// Without polling the read can't be called off, it ends with the next line
+#[cfg(not(unix))]
+fn wait_for_line(_requests: &Receiver<Request>, _queued: &mut VecDeque<Request>) -> bool {
+    true
+}
+
+/// Throws away what was typed but not entered yet
+fn discard_typed() {
+    #[cfg(unix)]
+    // SAFETY: tcflush only takes a file descriptor and a constant, and fails harmlessly if stdin isn't a terminal
+    unsafe {
+        libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH);
+    }
+}
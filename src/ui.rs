@@ -0,0 +1,600 @@
+use std::collections::VecDeque;
+use std::io::{self, Write, stdin, stdout};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use better_term::style::{Color, Style};
+use crossterm::execute;
+use crossterm::cursor::{MoveTo, Show};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal::{SetSize, SetTitle, disable_raw_mode, size};
+use crossterm::tty::IsTty;
+use crate::history::History;
+use crate::session::{InputReader, SessionEvent};
+use crate::{clear_term, get_input, read_password};
+use crate::command::CommandMuncher;
+use completion::{Completer, Completion};
+use editor::Editor;
+use output::Line;
+use screen::Screen;
+
+pub mod completion;
+pub mod editor;
+pub mod markup;
+pub mod output;
+pub mod screen;
+pub mod style;
+pub mod widgets;
+
+/// The prompt in front of lines sent to the server
+const SESSION_PROMPT: &str = "> ";
+
+/// The newest version of the screen commands this client knows.
+/// Version 1 is `clear`, `cursor`, `size`, `print`, `println` and `commands`,
+/// version 2 adds `span`, `box`, `table`, `bar`, `rule`, `title` and `ui`.
+pub const UI_COMMANDS_VERSION: u32 = 2;
+
+/// What the commands in a server's `Message.data` do to the screen
+pub enum UiCommand {
+    Clear,
+    /// moves the cursor, only in the plain line interface where the server owns the layout
+    Cursor(u16, u16),
+    /// asks the terminal to resize itself
+    Size(u16, u16),
+    Print(String),
+    Println(String),
+    /// the commands the server understands, as usage lines, for completing the input line
+    Commands(Vec<String>),
+    /// text in a style like `bold+red`, see `style::parse_style`
+    Span { style: String, text: String },
+    /// lines with a border around them and a title in its top edge
+    Box { title: String, lines: Vec<String> },
+    /// rows of `|` separated cells under the column headers, see `widgets::table`
+    Table { columns: String, rows: Vec<String> },
+    Bar { label: String, value: f64, max: f64, width: Option<u16>, color: Option<String> },
+    /// a horizontal line across the output, with an optional title
+    Rule(String),
+    /// sets the terminal's title
+    Title(String),
+    /// the version of the screen commands the server uses
+    Version(u32),
+}
+
+/// Where the login flow asks its questions and reports on its progress
+pub trait Console {
+    /// Asks the user, hiding the answer if it is `secret`. Returns None if the user cancelled
+    fn ask(&mut self, prompt: &str, secret: bool) -> Option<String>;
+    fn say(&mut self, text: &str);
+}
+
+/// Prompts straight on the terminal, for before the session's `Ui` has started
+#[derive(Default)]
+pub struct PlainConsole {
+    /// everything said so far, for the full screen to start with
+    pub said: Vec<String>,
+}
+
+impl Console for PlainConsole {
+    fn ask(&mut self, prompt: &str, secret: bool) -> Option<String> {
+        if secret {
+            read_password(prompt)
+        } else {
+            Some(get_input(prompt))
+        }
+    }
+
+    fn say(&mut self, text: &str) {
+        println!("{}", text);
+        self.said.push(text.to_string());
+    }
+}
+
+enum Mode {
+    /// plain lines on stdout, for when the client isn't run in a terminal
+    Lines(InputReader),
+    Full(Box<Screen>),
+}
+
+/// The game session's user interface: full screen in a terminal, plain lines otherwise.
+/// It owns the session's event channel, so terminal events are dealt with before the session sees them.
+pub struct Ui {
+    mode: Mode,
+    events: Receiver<SessionEvent>,
+    events_tx: Sender<SessionEvent>,
+    /// typing a line that starts with this previews the search as it is typed
+    search_command: Option<String>,
+    completer: Completer,
+    /// without colors the full screen keeps only attributes like bold, plain lines get no styling at all
+    colors: bool,
+    /// server events that came in while a question was asked, handed to the session after it
+    held: VecDeque<SessionEvent>,
+}
+
+impl Ui {
+    /// Takes over the terminal if there is one, with `status` on the status line.
+    /// The full screen starts out showing `said`, which is already on the terminal otherwise,
+    /// and its input line recalls lines from `history`.
+    pub fn start(status: String, said: &[String], history: History) -> Self {
+        let (events_tx, events) = channel();
+
+        let screen = if stdin().is_tty() && stdout().is_tty() { Screen::enter().ok() } else { None };
+        let mode = match screen {
+            Some(mut screen) => {
+                screen.status = status;
+                screen.editor = Editor::new(history);
+                for text in said {
+                    screen.push(format!("{}\n", text).as_str(), Style::default());
+                }
+                spawn_terminal_reader(events_tx.clone());
+                Mode::Full(Box::new(screen))
+            }
+            None => Mode::Lines(InputReader::spawn(events_tx.clone())),
+        };
+
+        let ui = Self { mode, events, events_tx, search_command: None, completer: Completer::new(), colors: true, held: VecDeque::new() };
+        ui.render();
+        ui
+    }
+
+    /// Sets the command that searches the scrollback, e.g. `/search `, so it can be previewed while typed
+    pub fn set_search_command(&mut self, command: String) {
+        self.search_command = Some(command);
+    }
+
+    /// Turns colors off for terminals without them
+    pub fn set_colors(&mut self, colors: bool) {
+        self.colors = colors;
+    }
+
+    /// Offers the client's own commands for completion, typed after `prefix`
+    pub fn set_local_commands<T>(&mut self, prefix: &str, commands: &CommandMuncher<T>) {
+        self.completer.set_local(prefix, commands);
+    }
+
+    /// A sender for events from other threads, like the server's
+    pub fn sender(&self) -> Sender<SessionEvent> {
+        self.events_tx.clone()
+    }
+
+    fn render(&self) {
+        if let Mode::Full(screen) = &self.mode {
+            let _ = screen.render();
+        }
+    }
+
+    /// Waits for the next event the session has to handle. Returns None if every sender is gone
+    pub fn next_event(&mut self) -> Option<SessionEvent> {
+        if let Some(event) = self.held.pop_front() {
+            return Some(event);
+        }
+        loop {
+            let event = self.events.recv().ok()?;
+            if let Some(event) = self.handle(event) {
+                return Some(event);
+            }
+        }
+    }
+
+    fn handle(&mut self, event: SessionEvent) -> Option<SessionEvent> {
+        match (&mut self.mode, event) {
+            (Mode::Lines(input), event @ SessionEvent::Input(_)) => {
+                input.line_received();
+                Some(event)
+            }
+            (Mode::Full(screen), SessionEvent::Terminal(Event::Resize(width, height))) => {
+                screen.resize(width, height);
+                let _ = screen.render();
+                None
+            }
+            (Mode::Full(_), SessionEvent::Terminal(Event::Key(key))) => self.key(key),
+            (_, SessionEvent::Terminal(_)) => None,
+            (_, event) => Some(event),
+        }
+    }
+
+    /// Edits the input line, returning the event a key finishes
+    fn key(&mut self, key: KeyEvent) -> Option<SessionEvent> {
+        let screen = match &mut self.mode {
+            Mode::Full(screen) => screen,
+            Mode::Lines(_) => return None,
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let previewing = self.search_command.as_ref().is_some_and(|command| screen.editor.text().starts_with(command.as_str()));
+
+        let event = match key.code {
+            KeyCode::Enter => {
+                let line = screen.editor.take();
+                // searches aren't echoed, or they would find themselves
+                if !screen.secret && !previewing {
+                    screen.push(format!("{}{}\n", screen.prompt, line).as_str(), Style::default().dim());
+                }
+                if let Err(e) = screen.editor.remember(line.as_str()) {
+                    screen.push(format!("{}\n", e).as_str(), Color::Red.to_style_fg());
+                }
+                Some(SessionEvent::Input(line))
+            }
+            KeyCode::PageUp => {
+                screen.page(1);
+                None
+            }
+            KeyCode::PageDown => {
+                screen.page(-1);
+                None
+            }
+            KeyCode::End if ctrl => {
+                screen.scroll_to_bottom();
+                None
+            }
+            KeyCode::Home if ctrl => {
+                screen.scroll_to_top();
+                None
+            }
+            KeyCode::Esc if screen.editor.is_searching() => {
+                screen.editor.cancel_search();
+                None
+            }
+            KeyCode::Esc => {
+                // a search being typed is cancelled along with its highlight
+                if previewing {
+                    screen.editor.clear();
+                }
+                screen.clear_search();
+                screen.scroll_to_bottom();
+                None
+            }
+            // raw mode swallows the signal, so Ctrl+C arrives as a key
+            KeyCode::Char('c') if ctrl => Some(SessionEvent::Interrupted),
+            KeyCode::Char('d') if ctrl && screen.editor.is_empty() => Some(SessionEvent::InputClosed),
+            // only command names are completed, so the cursor has to be after them
+            KeyCode::Tab if screen.editor.recall && screen.editor.at_end() && !screen.editor.is_searching() => {
+                match self.completer.complete(screen.editor.text().as_str()) {
+                    Completion::Line(line) => screen.editor.set(line.as_str()),
+                    Completion::Candidates(names) => screen.push(format!("{}\n", names.join("  ")).as_str(), Style::default().dim()),
+                    Completion::None => {}
+                }
+                None
+            }
+            _ => {
+                screen.editor.key(key);
+                None
+            }
+        };
+
+        // the search follows the line as it is typed, and goes away if the line stops being a search
+        if let Some(command) = &self.search_command {
+            match screen.editor.text().strip_prefix(command.as_str()) {
+                Some(term) => {
+                    let term = term.to_string();
+                    screen.preview_search(term.as_str());
+                }
+                None if previewing && event.is_none() => {
+                    screen.clear_search();
+                    screen.scroll_to_bottom();
+                }
+                None => {}
+            }
+        }
+        update_hint(screen, &self.completer);
+        let _ = screen.render();
+        event
+    }
+
+    /// Asks for the next line to send to the server
+    pub fn request_line(&mut self) {
+        match &mut self.mode {
+            Mode::Lines(input) => {
+                show_prompt();
+                input.request_line();
+            }
+            Mode::Full(screen) => {
+                screen.prompt = SESSION_PROMPT.to_string();
+                screen.secret = false;
+                screen.editor.recall = true;
+                let _ = screen.render();
+            }
+        }
+    }
+
+    /// Shows the prompt again after output, the full screen always shows it
+    pub fn show_prompt(&self) {
+        if let Mode::Lines(_) = self.mode {
+            show_prompt();
+        }
+    }
+
+    /// Throws away what the user typed before a question, like while disconnected, so it isn't taken
+    /// as the answer. Returns false if the input was closed or the user interrupted meanwhile.
+    fn discard_input(&mut self) -> bool {
+        let cancelled = match &mut self.mode {
+            Mode::Lines(input) => input.cancel(),
+            Mode::Full(screen) => {
+                screen.editor.clear();
+                false
+            }
+        };
+        // piped input can't be called off, and is kept anyway as it holds the answers
+        if !cancelled && matches!(self.mode, Mode::Lines(_)) {
+            return true;
+        }
+        loop {
+            // a called off read is waited for, keys pressed before are already queued
+            let event = if cancelled { self.events.recv().ok() } else { self.events.try_recv().ok() };
+            match event {
+                Some(SessionEvent::InputCancelled) => return true,
+                Some(SessionEvent::InputClosed) | Some(SessionEvent::Interrupted) => return false,
+                Some(event @ SessionEvent::Terminal(Event::Resize(_, _))) => {
+                    self.handle(event);
+                }
+                Some(event @ SessionEvent::Server(_)) => self.held.push_back(event),
+                Some(_) => {}
+                None => return !cancelled,
+            }
+        }
+    }
+
+    /// Asks the user a question in the middle of the session. Server events that come in meanwhile
+    /// are held back, `next_event` hands them out after the question.
+    /// Returns None if the input was closed or the user interrupted.
+    pub fn prompt(&mut self, prompt: &str, secret: bool) -> Option<String> {
+        if !self.discard_input() {
+            return None;
+        }
+
+        match &mut self.mode {
+            Mode::Lines(input) => {
+                if secret {
+                    input.request_secret(prompt);
+                } else {
+                    print!("{}", prompt);
+                    let _ = io::stdout().flush();
+                    input.request_line();
+                }
+            }
+            Mode::Full(screen) => {
+                // answers to questions aren't kept, and the history isn't offered as answers
+                screen.prompt = prompt.to_string();
+                screen.secret = secret;
+                screen.editor.recall = false;
+                let _ = screen.render();
+            }
+        }
+
+        let answer = loop {
+            let event = match self.events.recv() {
+                Ok(event) => self.handle(event),
+                Err(_) => break None,
+            };
+            match event {
+                Some(SessionEvent::Input(line)) => break Some(line),
+                Some(SessionEvent::InputClosed) | Some(SessionEvent::Interrupted) => break None,
+                Some(event @ SessionEvent::Server(_)) => self.held.push_back(event),
+                Some(_) | None => {}
+            }
+        };
+        if let Mode::Full(screen) = &mut self.mode {
+            screen.prompt = SESSION_PROMPT.to_string();
+            screen.secret = false;
+            screen.editor.recall = true;
+            let _ = screen.render();
+        }
+        answer
+    }
+
+    /// Prints text the way `print!` would, without any escape sequences in it
+    pub fn print(&mut self, text: &str) {
+        self.print_styled(text, Style::default());
+    }
+
+    pub fn println(&mut self, text: &str) {
+        self.print(format!("{}\n", text).as_str());
+    }
+
+    /// Prints text in `style` without ending the line
+    pub fn print_styled(&mut self, text: &str, style: Style) {
+        self.push_styled(text, style);
+        self.render();
+    }
+
+    /// Prints text with style markup, see `markup::parse`
+    pub fn print_markup(&mut self, text: &str) {
+        for span in markup::parse(markup::strip_escapes(text).as_str()) {
+            self.push_styled(span.text.as_str(), span.style);
+        }
+        self.render();
+    }
+
+    fn push_styled(&mut self, text: &str, style: Style) {
+        let text = markup::strip_escapes(text);
+        match &mut self.mode {
+            Mode::Lines(_) if !self.colors || style == Style::default() => {
+                print!("{}", text);
+                let _ = io::stdout().flush();
+            }
+            Mode::Lines(_) => {
+                print!("{}{}{}", style, text, Style::default().overwrite());
+                let _ = io::stdout().flush();
+            }
+            Mode::Full(screen) if !self.colors => screen.push(text.as_str(), Style { fg: None, bg: None, ..style }),
+            Mode::Full(screen) => screen.push(text.as_str(), style),
+        }
+    }
+
+    /// Prints whole lines, like the ones from `widgets`
+    pub fn print_lines(&mut self, lines: Vec<Line>) {
+        for line in lines {
+            for span in line.spans {
+                self.print_styled(span.text.as_str(), span.style);
+            }
+            self.print("\n");
+        }
+    }
+
+    /// Prints a line in red
+    pub fn error(&mut self, text: &str) {
+        self.print_styled(text, Color::Red.to_style_fg());
+        self.print("\n");
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.mode {
+            Mode::Lines(_) => clear_term(),
+            Mode::Full(screen) => {
+                screen.clear();
+                let _ = screen.render();
+            }
+        }
+    }
+
+    /// Whether output is kept to scroll back through and search
+    pub fn has_scrollback(&self) -> bool {
+        matches!(self.mode, Mode::Full(_))
+    }
+
+    /// Scrolls to the next older match of `term` and highlights it, or clears the search if `term` is empty
+    pub fn search(&mut self, term: &str) -> Result<(), String> {
+        let screen = match &mut self.mode {
+            Mode::Full(screen) => screen,
+            Mode::Lines(_) => return Err("There is no scrollback to search outside the full-screen interface".to_string()),
+        };
+        let result = if term.is_empty() {
+            screen.clear_search();
+            screen.scroll_to_bottom();
+            Ok(())
+        } else {
+            screen.search(term)
+        };
+        let _ = screen.render();
+        result
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        if let Mode::Full(screen) = &mut self.mode {
+            screen.status = status;
+            let _ = screen.render();
+        }
+    }
+
+    /// The size of the area output is shown in
+    pub fn pane_size(&self) -> (u16, u16) {
+        match &self.mode {
+            // there is no size to report when the output isn't a terminal
+            Mode::Lines(_) => size().unwrap_or((80, 24)),
+            Mode::Full(screen) => screen.output_size(),
+        }
+    }
+
+    pub fn apply(&mut self, command: UiCommand) {
+        match command {
+            UiCommand::Clear => self.clear(),
+            UiCommand::Print(text) => self.print(text.as_str()),
+            UiCommand::Println(text) => self.println(text.as_str()),
+            UiCommand::Cursor(x, y) => {
+                // the full screen lays itself out, moving its cursor would only garble it
+                if let Mode::Lines(_) = self.mode {
+                    let _ = execute!(stdout(), MoveTo(x, y));
+                }
+            }
+            UiCommand::Commands(usages) => {
+                if let Err(e) = self.completer.set_server(usages.as_slice()) {
+                    self.error(e.as_str());
+                }
+                if let Mode::Full(screen) = &mut self.mode {
+                    update_hint(screen, &self.completer);
+                    let _ = screen.render();
+                }
+            }
+            UiCommand::Size(width, height) => {
+                let _ = execute!(stdout(), SetSize(width, height));
+                if let Mode::Full(screen) = &mut self.mode {
+                    let (width, height) = size().unwrap_or((width, height));
+                    screen.resize(width, height);
+                    let _ = screen.render();
+                }
+            }
+            UiCommand::Span { style, text } => match style::parse_style(style.as_str()) {
+                Ok(style) => self.print_styled(text.as_str(), style),
+                Err(e) => self.error(e.as_str()),
+            },
+            UiCommand::Box { title, lines } => {
+                let width = self.pane_size().0 as usize;
+                self.print_lines(widgets::boxed(title.as_str(), lines.as_slice(), width));
+            }
+            UiCommand::Table { columns, rows } => self.print_lines(widgets::table(columns.as_str(), rows.as_slice())),
+            UiCommand::Bar { label, value, max, width, color } => {
+                // NaN and infinities can't be drawn, and without a max above 0 there's nothing to fill
+                if !value.is_finite() || !max.is_finite() || max <= 0.0 {
+                    return self.error(format!("Can't draw the bar '{}' at {} of {}, it needs finite numbers and a max above 0", label, value, max).as_str());
+                }
+                let color = match color.map(|color| style::parse_color(color.as_str())).transpose() {
+                    Ok(color) => color,
+                    Err(e) => return self.error(e.as_str()),
+                };
+                // leave room for the label and the numbers next to the bar
+                let room = (self.pane_size().0 as usize).saturating_sub(label.chars().count() + 16).max(1);
+                let width = (width.unwrap_or(widgets::DEFAULT_BAR_WIDTH) as usize).min(room);
+                self.print_lines(vec![widgets::bar(label.as_str(), value, max, width, color)]);
+            }
+            UiCommand::Rule(title) => {
+                let width = self.pane_size().0 as usize;
+                self.print_lines(vec![widgets::rule(title.as_str(), width)]);
+            }
+            UiCommand::Title(title) => {
+                // piped output has no title, the escape would only end up in it
+                if stdout().is_tty() {
+                    let _ = execute!(stdout(), SetTitle(markup::title(title.as_str())));
+                }
+            }
+            UiCommand::Version(version) => {
+                if version > UI_COMMANDS_VERSION {
+                    self.error(format!("The server uses version {} of the screen commands, this client knows version {}; some screens may not draw", version, UI_COMMANDS_VERSION).as_str());
+                }
+            }
+        }
+    }
+
+    /// Gives the terminal back: leaves the full screen, and resets colors and the cursor
+    pub fn leave(&mut self) {
+        if let Mode::Full(screen) = &mut self.mode {
+            screen.leave();
+        }
+        let _ = disable_raw_mode();
+        print!("{}", Style::default().overwrite());
+        let _ = execute!(stdout(), Show);
+    }
+}
+
+impl Console for Ui {
+    fn ask(&mut self, prompt: &str, secret: bool) -> Option<String> {
+        self.prompt(prompt, secret)
+    }
+
+    fn say(&mut self, text: &str) {
+        self.println(text);
+    }
+}
+
+/// Hints at what could follow the input line, while the cursor is at its end
+fn update_hint(screen: &mut Screen, completer: &Completer) {
+    let editor = &screen.editor;
+    screen.hint = if editor.recall && editor.at_end() && !editor.is_searching() {
+        completer.hint(editor.text().as_str())
+    } else {
+        None
+    };
+}
+
+/// Shows the input prompt in the plain line interface
+fn show_prompt() {
+    print!("{}", SESSION_PROMPT);
+    let _ = io::stdout().flush();
+}
+
+/// Forwards key presses and resizes to the session's events, for the full screen
+fn spawn_terminal_reader(events: Sender<SessionEvent>) {
+    thread::spawn(move || {
+        while let Ok(event) = read() {
+            if events.send(SessionEvent::Terminal(event)).is_err() {
+                break;
+            }
+        }
+    });
+}
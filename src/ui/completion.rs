@@ -0,0 +1,186 @@
+use crate::command::CommandMuncher;
+use crate::command::signature::Signature;
+use crate::local::{Input, classify};
+
+/// What pressing Tab does to the input line
+#[derive(PartialEq, Debug)]
+pub enum Completion {
+    /// nothing matches
+    None,
+    /// the line with the command name completed as far as it is unambiguous
+    Line(String),
+    /// the commands the name could still become, as they would be typed
+    Candidates(Vec<String>),
+}
+
+/// Completes command names and hints at their arguments, for the commands the server
+/// advertised and the client's own. Both are kept as registries of commands that do nothing.
+pub struct Completer {
+    /// the local command prefix, empty if there are no local commands
+    prefix: String,
+    local: CommandMuncher<()>,
+    server: CommandMuncher<()>,
+}
+
+impl Default for Completer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Completer {
+    pub fn new() -> Self {
+        Self {
+            prefix: String::new(),
+            local: CommandMuncher::new(),
+            server: CommandMuncher::new(),
+        }
+    }
+
+    /// Offers the commands of `commands`, which are typed after `prefix`
+    pub fn set_local<T>(&mut self, prefix: &str, commands: &CommandMuncher<T>) {
+        self.prefix = prefix.to_string();
+        self.local = copy_commands(commands);
+    }
+
+    /// Replaces the server's commands with the ones in `usages`, given as usage lines like `go <direction: text>`.
+    /// Lines that can't be read are left out and reported.
+    pub fn set_server(&mut self, usages: &[String]) -> Result<(), String> {
+        self.server = CommandMuncher::new();
+        let mut errors = Vec::new();
+        for usage in usages {
+            match Signature::from_usage(usage.as_str()) {
+                Ok((name, signature)) => {
+                    self.server.register_typed(name, signature, |_args| ());
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("The server advertised commands that can't be completed:\n{}", errors.join("\n")))
+        }
+    }
+
+    /// The registry `line` is completed from, the part before the command and the command with its arguments
+    fn split<'a>(&self, line: &'a str) -> (&CommandMuncher<()>, &'a str, &'a str) {
+        match classify(line, self.prefix.as_str()) {
+            Input::Local(rest) => (&self.local, &line[..line.len() - rest.len()], rest),
+            Input::Server(rest) => (&self.server, &line[..line.len() - rest.len()], rest),
+        }
+    }
+
+    /// Completes the command name at the start of `line`. Arguments aren't completed.
+    pub fn complete(&self, line: &str) -> Completion {
+        let (commands, lead, typed) = self.split(line);
+        if typed.contains(char::is_whitespace) {
+            return Completion::None;
+        }
+        let candidates = commands.complete(typed);
+        match candidates.as_slice() {
+            [] => Completion::None,
+            [name] => Completion::Line(format!("{}{} ", lead, name)),
+            [first, others @ ..] => {
+                // the longest start they all share
+                let common = others.iter().fold(first.len(), |common, name| {
+                    first.bytes().zip(name.bytes()).take(common).take_while(|(a, b)| a == b).count()
+                });
+                if common > typed.len() {
+                    Completion::Line(format!("{}{}", lead, &first[..common]))
+                } else {
+                    Completion::Candidates(candidates.iter().map(|name| format!("{}{}", lead, name)).collect())
+                }
+            }
+        }
+    }
+
+    /// What could follow `line`: the rest of the only command it can become with its arguments,
+    /// or the arguments that haven't been typed yet
+    pub fn hint(&self, line: &str) -> Option<String> {
+        let (commands, _, typed) = self.split(line);
+        let mut words = typed.split_whitespace();
+        let command = words.next()?;
+
+        let hint = if typed.contains(char::is_whitespace) {
+            let signature = commands.signature(command)?;
+            let hint = signature.hint(words.count());
+            // the word being typed counts, the hint starts after it
+            if typed.ends_with(char::is_whitespace) { hint } else { format!(" {}", hint) }
+        } else {
+            // a command that is typed out wins over the longer ones it starts
+            let candidates = commands.complete(command);
+            let name = match candidates.as_slice() {
+                _ if candidates.contains(&command) => command,
+                [name] => name,
+                _ => return None,
+            };
+            let usage = commands.signature(name).map(|signature| signature.usage("")).unwrap_or_default();
+            format!("{}{}", &name[command.len()..], usage)
+        };
+        if hint.trim().is_empty() { None } else { Some(hint) }
+    }
+}
+
+/// A registry with the signatures and infos of `commands`, whose handlers do nothing
+fn copy_commands<T>(commands: &CommandMuncher<T>) -> CommandMuncher<()> {
+    let mut copy = CommandMuncher::new();
+    for info in commands.list() {
+        let name = info.name.as_str();
+        let copied = match commands.signature(name) {
+            Some(signature) => copy.register_typed(name, signature.clone(), |_args| ()),
+            None => copy.register(name, |_args| ()),
+        };
+        *copied = info.clone();
+    }
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::signature::ArgType;
+
+    fn completer() -> Completer {
+        let mut local = CommandMuncher::new();
+        local.register_typed("quit", Signature::new().variadic("reason", ArgType::Text), |_args| ());
+        local.register_typed("reconnect", Signature::new(), |_args| ());
+        let mut completer = Completer::default();
+        completer.set_local("/", &local);
+        let usages = ["look", "go <direction: text>", "give <item: text> [count: u32]", "gossip [words: text...]"];
+        completer.set_server(&usages.iter().map(|usage| usage.to_string()).collect::<Vec<String>>()).unwrap();
+        completer
+    }
+
+    #[test]
+    fn completes_command_names() {
+        let completer = completer();
+        assert_eq!(completer.complete("lo"), Completion::Line("look ".to_string()));
+        assert_eq!(completer.complete("g"), Completion::Candidates(vec!["give".to_string(), "go".to_string(), "gossip".to_string()]));
+        assert_eq!(completer.complete("gos"), Completion::Line("gossip ".to_string()));
+        assert_eq!(completer.complete("/re"), Completion::Line("/reconnect ".to_string()));
+        assert_eq!(completer.complete("/"), Completion::Candidates(vec!["/quit".to_string(), "/reconnect".to_string()]));
+        assert_eq!(completer.complete("go north"), Completion::None);
+        assert_eq!(completer.complete("//q"), Completion::None);
+
+        let mut completer = completer;
+        let err = completer.set_server(&["dance <style: moves>".to_string(), "wave".to_string()]).unwrap_err();
+        assert!(err.ends_with("Unknown argument type 'moves' in 'dance <style: moves>'"));
+        assert_eq!(completer.complete("w"), Completion::Line("wave ".to_string()));
+        assert_eq!(completer.complete("lo"), Completion::None);
+    }
+
+    #[test]
+    fn hints_at_arguments() {
+        let completer = completer();
+        assert_eq!(completer.hint("gi").as_deref(), Some("ve <item: text> [count: u32]"));
+        assert_eq!(completer.hint("give ").as_deref(), Some("<item: text> [count: u32]"));
+        assert_eq!(completer.hint("give sword").as_deref(), Some(" [count: u32]"));
+        assert_eq!(completer.hint("give sword 2"), None);
+        assert_eq!(completer.hint("/q").as_deref(), Some("uit [reason: text...]"));
+        assert_eq!(completer.hint("look"), None);
+        assert_eq!(completer.hint("g"), None);
+        assert_eq!(completer.hint("go").as_deref(), Some(" <direction: text>"));
+        assert_eq!(completer.hint("dance "), None);
+    }
+}
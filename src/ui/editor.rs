@@ -0,0 +1,354 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::history::History;
+
+/// A reverse incremental search through the history, started with Ctrl+R
+struct ReverseSearch {
+    term: String,
+    /// the history entry that matches, if any does
+    found: Option<usize>,
+    /// the line from before the search, put back if it is cancelled
+    original: Vec<char>,
+}
+
+/// The input line being edited, with the cursor and the history it can recall
+pub struct Editor {
+    text: Vec<char>,
+    /// where the cursor is, in characters
+    cursor: usize,
+    /// whether earlier lines can be recalled, which prompts like the password turn off
+    pub recall: bool,
+    history: History,
+    /// the history entry being edited, None while on the new line
+    browsing: Option<usize>,
+    /// the new line, kept while browsing the history
+    draft: Vec<char>,
+    search: Option<ReverseSearch>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new(History::new())
+    }
+}
+
+impl Editor {
+    pub fn new(history: History) -> Self {
+        Self {
+            text: Vec::new(),
+            cursor: 0,
+            recall: true,
+            history,
+            browsing: None,
+            draft: Vec::new(),
+            search: None,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Whether the cursor is at the end of the line
+    pub fn at_end(&self) -> bool {
+        self.cursor == self.text.len()
+    }
+
+    /// Replaces the line, putting the cursor at its end
+    pub fn set(&mut self, text: &str) {
+        self.set_text(text.chars().collect());
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text(Vec::new());
+    }
+
+    fn set_text(&mut self, text: Vec<char>) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    /// Finishes the line, returning it and starting a new one
+    pub fn take(&mut self) -> String {
+        self.accept_search();
+        self.browsing = None;
+        self.draft.clear();
+        self.cursor = 0;
+        std::mem::take(&mut self.text).into_iter().collect()
+    }
+
+    /// Adds a finished line to the history, if lines are being recalled
+    pub fn remember(&mut self, line: &str) -> Result<(), String> {
+        if self.recall {
+            self.history.push(line)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ends the reverse search, keeping the line it found
+    fn accept_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            if let Some(entry) = search.found.and_then(|found| self.history.get(found)) {
+                self.set_text(entry.chars().collect());
+            }
+        }
+    }
+
+    /// Ends the reverse search, putting back the line from before it
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.set_text(search.original);
+        }
+    }
+
+    /// Looks for the search term in entries before `before`. With `keep_last`, a failed search keeps the last match
+    fn search_history(&mut self, before: usize, keep_last: bool) {
+        if let Some(search) = &mut self.search {
+            let found = if search.term.is_empty() { None } else { self.history.find(search.term.as_str(), before) };
+            if found.is_some() || !keep_last {
+                search.found = found;
+            }
+        }
+    }
+
+    /// Edits the line with a key. Returns false for keys it doesn't use
+    pub fn key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+        if let Some(search) = &mut self.search {
+            match key.code {
+                KeyCode::Char('r') if ctrl => {
+                    let before = search.found.unwrap_or_else(|| self.history.len());
+                    self.search_history(before, true);
+                    return true;
+                }
+                KeyCode::Char('g') if ctrl => {
+                    self.cancel_search();
+                    return true;
+                }
+                KeyCode::Char(c) if !ctrl && !alt => {
+                    search.term.push(c);
+                    // the longer term can still match the entry already found
+                    let before = search.found.map_or(self.history.len(), |found| found + 1);
+                    self.search_history(before, false);
+                    return true;
+                }
+                KeyCode::Backspace => {
+                    search.term.pop();
+                    let len = self.history.len();
+                    self.search_history(len, false);
+                    return true;
+                }
+                // anything else edits the line that was found
+                _ => self.accept_search(),
+            }
+        }
+
+        match key.code {
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('b') if ctrl => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('f') if ctrl => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Backspace if alt => self.delete_word(),
+            KeyCode::Char('w') if ctrl => self.delete_word(),
+            KeyCode::Char('h') if ctrl => self.backspace(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Char('d') if ctrl => self.delete(),
+            KeyCode::Char('u') if ctrl => {
+                self.text.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if ctrl => self.text.truncate(self.cursor),
+            KeyCode::Up if self.recall => self.older(),
+            KeyCode::Down if self.recall => self.newer(),
+            KeyCode::Char('p') if ctrl && self.recall => self.older(),
+            KeyCode::Char('n') if ctrl && self.recall => self.newer(),
+            KeyCode::Char('r') if ctrl && self.recall => {
+                self.search = Some(ReverseSearch { term: String::new(), found: None, original: self.text.clone() });
+            }
+            KeyCode::Char(c) if !ctrl && !alt => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Where the word before the cursor starts, skipping the spaces in front of the cursor
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        while start > 0 && self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Where the word after the cursor ends
+    fn word_end(&self) -> usize {
+        let mut end = self.cursor;
+        while end < self.text.len() && self.text[end].is_whitespace() {
+            end += 1;
+        }
+        while end < self.text.len() && !self.text[end].is_whitespace() {
+            end += 1;
+        }
+        end
+    }
+
+    fn delete_word(&mut self) {
+        let start = self.word_start();
+        self.text.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Shows the previous history entry, keeping the new line to come back to
+    fn older(&mut self) {
+        let index = match self.browsing {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.browsing = Some(index);
+        let entry = self.history.get(index).unwrap_or_default().chars().collect();
+        self.set_text(entry);
+    }
+
+    fn newer(&mut self) {
+        let index = match self.browsing {
+            Some(index) => index + 1,
+            None => return,
+        };
+        let entry = match self.history.get(index) {
+            Some(entry) => {
+                self.browsing = Some(index);
+                entry.chars().collect()
+            }
+            None => {
+                self.browsing = None;
+                std::mem::take(&mut self.draft)
+            }
+        };
+        self.set_text(entry);
+    }
+
+    /// The input line as shown after `prompt`, with the column of the cursor.
+    /// A `secret` line is masked, and a reverse search shows its own prompt.
+    pub fn view(&self, prompt: &str, secret: bool) -> (String, usize) {
+        if let Some(search) = &self.search {
+            let state = if search.found.is_none() && !search.term.is_empty() { "failed reverse-i-search" } else { "reverse-i-search" };
+            let found = search.found.and_then(|found| self.history.get(found)).unwrap_or_default();
+            let line = format!("({})'{}': {}", state, search.term, found);
+            let cursor = line.chars().count();
+            return (line, cursor);
+        }
+
+        let typed = if secret { "*".repeat(self.text.len()) } else { self.text() };
+        let cursor = prompt.chars().count() + self.cursor;
+        (format!("{}{}", prompt, typed), cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut Editor, code: KeyCode, modifiers: KeyModifiers) {
+        editor.key(KeyEvent { code, modifiers });
+    }
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            press(editor, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    #[test]
+    fn cursor_moves_and_words_are_deleted() {
+        let mut editor = Editor::default();
+        type_text(&mut editor, "say hello there");
+        press(&mut editor, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut editor, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(editor.text(), "say there");
+        press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+        type_text(&mut editor, "/");
+        press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "/say ther");
+        assert_eq!(editor.view("> ", false), ("> /say ther".to_string(), 11));
+        assert_eq!(editor.take(), "/say ther");
+        assert!(editor.is_empty());
+    }
+
+    #[test]
+    fn history_is_browsed_and_searched() {
+        let mut editor = Editor::default();
+        for line in ["look", "go north", "say hi"].iter() {
+            type_text(&mut editor, line);
+            let line = editor.take();
+            editor.remember(line.as_str()).unwrap();
+        }
+
+        type_text(&mut editor, "draft");
+        press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "go north");
+        press(&mut editor, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(editor.text(), "draft");
+
+        press(&mut editor, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut editor, "o");
+        assert_eq!(editor.view("> ", false).0, "(reverse-i-search)'o': go north");
+        press(&mut editor, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(editor.view("> ", false).0, "(reverse-i-search)'o': look");
+        // no older match keeps the last one
+        press(&mut editor, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(editor.view("> ", false).0, "(reverse-i-search)'o': look");
+        press(&mut editor, KeyCode::End, KeyModifiers::NONE);
+        type_text(&mut editor, "!");
+        assert_eq!(editor.text(), "look!");
+
+        press(&mut editor, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        type_text(&mut editor, "x");
+        assert_eq!(editor.view("> ", false).0, "(failed reverse-i-search)'x': ");
+        editor.cancel_search();
+        assert_eq!(editor.text(), "look!");
+    }
+}
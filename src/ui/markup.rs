@@ -0,0 +1,162 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use better_term::style::Style;
+use crate::ui::output::Span;
+use crate::ui::style::parse_style;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+/// the single character form of `ESC [`
+const CSI: char = '\u{9b}';
+
+/// Removes terminal escape sequences and control characters, keeping newlines and tabs.
+/// Servers style their text with markup instead, so nothing they send can move the cursor or retitle the terminal.
+pub fn strip_escapes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '\t' => stripped.push(c),
+            ESC => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                // strings like window titles run until BEL or `ESC \`
+                Some(']') | Some('P') | Some('X') | Some('^') | Some('_') => skip_string(&mut chars),
+                _ => {}
+            },
+            CSI => skip_csi(&mut chars),
+            c if c.is_control() => {}
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// The most characters a server can put in the terminal's title
+pub const MAX_TITLE_LENGTH: usize = 100;
+
+/// Makes a server's text safe to set as the terminal's title: a single line without escapes,
+/// which would otherwise end the title early and run whatever follows, at most `MAX_TITLE_LENGTH` long
+pub fn title(text: &str) -> String {
+    strip_escapes(text).chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_TITLE_LENGTH)
+        .collect()
+}
+
+/// Skips the parameters of a control sequence and its final byte
+fn skip_csi(chars: &mut Peekable<Chars>) {
+    chars.find(|c| ('\x40'..='\x7e').contains(c));
+}
+
+fn skip_string(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL => break,
+            ESC if chars.peek() == Some(&'\\') => {
+                chars.next();
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Splits text with style markup into spans. A tag like `{red}`, `{bold+bg:blue}`, `{208}` or `{#ff8800}`
+/// adds to the current style, see `style::apply_word`, and `{reset}` goes back to the plain style.
+/// `{{` is a literal brace, and anything in braces that isn't a style is kept as it is.
+pub fn parse(text: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Style::default();
+    let mut rest = text;
+    let mut push = |text: &str, style: Style| match spans.last_mut() {
+        Some(span) if span.style == style => span.text.push_str(text),
+        _ if text.is_empty() => {}
+        _ => spans.push(Span { text: text.to_string(), style }),
+    };
+
+    while let Some(open) = rest.find('{') {
+        push(&rest[..open], style);
+        rest = &rest[open + 1..];
+        if let Some(after) = rest.strip_prefix('{') {
+            push("{", style);
+            rest = after;
+            continue;
+        }
+        let tag = rest.find('}').map(|close| (&rest[..close], close));
+        match tag.and_then(|(tag, close)| Some((apply_tag(style, tag)?, close))) {
+            Some((styled, close)) => {
+                style = styled;
+                rest = &rest[close + 1..];
+            }
+            None => push("{", style),
+        }
+    }
+    push(rest, style);
+    spans
+}
+
+fn apply_tag(style: Style, tag: &str) -> Option<Style> {
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return None;
+    }
+    // the tag's words change the current style, rather than starting a new one
+    parse_style(tag).ok().map(|tagged| if tag.split('+').any(|word| word.eq_ignore_ascii_case("reset") || word.eq_ignore_ascii_case("plain")) {
+        tagged
+    } else {
+        merge(style, tagged)
+    })
+}
+
+/// `style` with everything `over` sets on top
+fn merge(style: Style, over: Style) -> Style {
+    Style {
+        fg: over.fg.or(style.fg),
+        bg: over.bg.or(style.bg),
+        bold: style.bold || over.bold,
+        dim: style.dim || over.dim,
+        italic: style.italic || over.italic,
+        underline: style.underline || over.underline,
+        blink: style.blink || over.blink,
+        invert: style.invert || over.invert,
+        hide: style.hide || over.hide,
+        strikethrough: style.strikethrough || over.strikethrough,
+        overwrite: style.overwrite || over.overwrite,
+    }
+}
+
+/// The text without its markup, as it reads on a terminal without colors
+pub fn plain(text: &str) -> String {
+    parse(text).into_iter().map(|span| span.text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use better_term::style::Color;
+
+    #[test]
+    fn escapes_are_stripped() {
+        assert_eq!(strip_escapes("\x1b[1;31mred\x1b[0m\r\n\ttab"), "red\n\ttab");
+        assert_eq!(strip_escapes("\x1b]0;evil title\x07ok\x1b]2;other\x1b\\"), "ok");
+        assert_eq!(strip_escapes("\u{9b}2Jgone\x1bc reset\x08"), "gone reset");
+        assert_eq!(strip_escapes("unterminated \x1b["), "unterminated ");
+    }
+
+    #[test]
+    fn titles_are_one_bounded_line() {
+        assert_eq!(title("x\x07\x1b[2J\x1b]0;inner\x07\nnext\tline"), "xnextline");
+        assert_eq!(title("a".repeat(500).as_str()).len(), MAX_TITLE_LENGTH);
+    }
+
+    #[test]
+    fn markup_becomes_spans() {
+        let spans = parse("{bold}{red}Danger{reset} ahead {{not a tag} {sparkly} {#00ff00+bg:17}go");
+        let texts = spans.iter().map(|span| span.text.as_str()).collect::<Vec<&str>>();
+        assert_eq!(texts, vec!["Danger", " ahead {not a tag} {sparkly} ", "go"]);
+        assert!(spans[0].style.bold && spans[0].style.fg == Some(Color::Red));
+        assert!(spans[1].style == Style::default());
+        assert!(spans[2].style.fg == Some(Color::RGB(0, 255, 0)) && spans[2].style.bg == Some(Color::Fixed(17)));
+
+        assert_eq!(plain("{red}HP{reset}: 10 {unclosed"), "HP: 10 {unclosed");
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use better_term::style::{Color, Style};
+
+/// How many lines of output are kept for scrolling back
+pub const OUTPUT_CAPACITY: usize = 5000;
+
+/// A run of text in a single style
+#[derive(Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// A line of output as it was printed, before it is wrapped to the screen
+#[derive(Clone, Default, PartialEq)]
+pub struct Line {
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    pub fn push(&mut self, text: &str, style: Style) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.text.push_str(text),
+            _ => self.spans.push(Span { text: text.to_string(), style }),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// How many rows the line takes when wrapped to `width`
+    fn row_count(&self, width: usize) -> usize {
+        let chars = self.spans.iter().map(|span| span.text.chars().count()).sum::<usize>();
+        chars.div_ceil(width.max(1)).max(1)
+    }
+
+    /// Whether the line contains `term`, ignoring ASCII case
+    fn contains(&self, term: &str) -> bool {
+        self.text().to_ascii_lowercase().contains(term.to_ascii_lowercase().as_str())
+    }
+
+    /// The line with every match of `term` highlighted, ignoring ASCII case
+    fn highlight(&self, term: &str) -> Line {
+        if term.is_empty() {
+            return self.clone();
+        }
+        let text = self.text().to_ascii_lowercase();
+        let term = term.to_ascii_lowercase();
+        let matches = text.match_indices(term.as_str()).map(|(start, m)| (start, start + m.len())).collect::<Vec<(usize, usize)>>();
+        if matches.is_empty() {
+            return self.clone();
+        }
+
+        let mut highlighted = Line::default();
+        let mut offset = 0;
+        for span in self.spans.iter() {
+            // cut the span where matches start and end, ASCII lowercasing keeps the byte offsets
+            let end = offset + span.text.len();
+            let mut cuts = vec![offset, end];
+            for &(start, stop) in matches.iter() {
+                cuts.extend([start, stop].iter().filter(|&&cut| cut > offset && cut < end));
+            }
+            cuts.sort_unstable();
+            cuts.dedup();
+
+            for pair in cuts.windows(2) {
+                let inside = matches.iter().any(|&(start, stop)| start <= pair[0] && pair[0] < stop);
+                let style = if inside { span.style.bg(Color::Yellow).fg(Color::Black) } else { span.style };
+                highlighted.push(&span.text[pair[0] - offset..pair[1] - offset], style);
+            }
+            offset = end;
+        }
+        highlighted
+    }
+
+    /// Splits the line into rows of at most `width` characters
+    pub fn wrap(&self, width: usize) -> Vec<Line> {
+        let width = width.max(1);
+        let mut rows = vec![Line::default()];
+        let mut used = 0;
+        for span in self.spans.iter() {
+            for c in span.text.chars() {
+                if used == width {
+                    rows.push(Line::default());
+                    used = 0;
+                }
+                let mut buf = [0u8; 4];
+                rows.last_mut().unwrap().push(c.encode_utf8(&mut buf), span.style);
+                used += 1;
+            }
+        }
+        rows
+    }
+}
+
+/// The session's output, kept as lines so it can be rewrapped when the terminal is resized
+pub struct Output {
+    lines: VecDeque<Line>,
+    /// whether the last line is still being printed, i.e. didn't end with a newline yet
+    open: bool,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Output {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            open: false,
+        }
+    }
+
+    /// Appends text the way `print!` would: newlines end lines,
+    /// and text after the last newline is continued by the next push
+    pub fn push(&mut self, text: &str, style: Style) {
+        // the pane is drawn cell by cell, so tabs and carriage returns can't move the cursor
+        let text = text.replace('\t', "    ").replace('\r', "");
+
+        let mut parts = text.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            let ends = parts.peek().is_some();
+            if !self.open {
+                if part.is_empty() && !ends {
+                    break;
+                }
+                if self.lines.len() == OUTPUT_CAPACITY {
+                    self.lines.pop_front();
+                }
+                self.lines.push_back(Line::default());
+            }
+            if !part.is_empty() {
+                self.lines.back_mut().unwrap().push(part, style);
+            }
+            self.open = !ends;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.open = false;
+    }
+
+    /// How many rows the whole output takes when wrapped to `width`
+    pub fn total_rows(&self, width: usize) -> usize {
+        self.lines.iter().map(|line| line.row_count(width)).sum()
+    }
+
+    /// `height` rows of output wrapped to `width`, oldest first, ending `scroll` rows above the bottom.
+    /// Matches of `highlight` are highlighted.
+    pub fn rows(&self, width: usize, height: usize, scroll: usize, highlight: Option<&str>) -> Vec<Line> {
+        let wanted = scroll + height;
+        let mut rows = Vec::with_capacity(wanted);
+        for line in self.lines.iter().rev() {
+            if rows.len() >= wanted {
+                break;
+            }
+            let line = match highlight {
+                Some(term) => line.highlight(term),
+                None => line.clone(),
+            };
+            rows.extend(line.wrap(width).into_iter().rev());
+        }
+        let mut rows = rows.into_iter().skip(scroll).take(height).collect::<Vec<Line>>();
+        rows.reverse();
+        rows
+    }
+
+    /// Finds the newest line containing `term` that is older than the line `after` rows above the bottom,
+    /// or the newest of all if `after` is None. Returns how far to scroll to put it at the bottom of the view.
+    pub fn find(&self, term: &str, width: usize, after: Option<usize>) -> Option<usize> {
+        let mut bottom = 0;
+        for line in self.lines.iter().rev() {
+            let older = after.is_none_or(|after| bottom > after);
+            if older && line.contains(term) {
+                return Some(bottom);
+            }
+            bottom += line.row_count(width);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.spans.iter().map(|span| span.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn push_continues_unfinished_lines() {
+        let mut output = Output::new();
+        output.push("> ", Style::default());
+        output.push("hello\nwor", Style::default());
+        output.push("ld\n\n", Style::default().bold());
+        output.push("\tlast", Style::default());
+        let lines = output.rows(80, 10, 0, None);
+        assert_eq!(texts(&lines), vec!["> hello", "world", "", "    last"]);
+        // a style change starts a new span
+        assert_eq!(lines[1].spans.len(), 2);
+    }
+
+    #[test]
+    fn rows_are_wrapped_from_the_bottom() {
+        let mut output = Output::new();
+        output.push("first\nabcdefgh\nlast\n", Style::default());
+        assert_eq!(texts(&output.rows(3, 4, 0, None)), vec!["def", "gh", "las", "t"]);
+        assert_eq!(texts(&output.rows(80, 10, 0, None)), vec!["first", "abcdefgh", "last"]);
+        assert_eq!(output.total_rows(3), 7);
+        // scrolled up by three rows
+        assert_eq!(texts(&output.rows(3, 2, 3, None)), vec!["abc", "def"]);
+    }
+
+    #[test]
+    fn search_finds_older_matches_and_highlights_them() {
+        let mut output = Output::new();
+        output.push("a goblin appears\nnothing here\nthe Goblin attacks\nquiet\n", Style::default());
+        assert_eq!(output.find("goblin", 80, None), Some(1));
+        assert_eq!(output.find("goblin", 80, Some(1)), Some(3));
+        assert_eq!(output.find("goblin", 80, Some(3)), None);
+        assert_eq!(output.find("dragon", 80, None), None);
+
+        let rows = output.rows(80, 1, 1, Some("goblin"));
+        let spans = rows[0].spans.iter().map(|span| span.text.as_str()).collect::<Vec<&str>>();
+        assert_eq!(spans, vec!["the ", "Goblin", " attacks"]);
+        assert!(rows[0].spans[1].style.bg.is_some());
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{self, Write, stdout};
+use better_term::style::Style;
+use crossterm::{execute, queue};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, enable_raw_mode, disable_raw_mode, size};
+use crate::ui::editor::Editor;
+use crate::ui::output::Output;
+
+/// How far back the search is, and whether it has been confirmed
+struct Search {
+    term: String,
+    /// where the last confirmed match was, None while the term is still being typed
+    found_at: Option<usize>,
+}
+
+/// The full-screen layout: the output pane on top, then a status line, then the input line
+pub struct Screen {
+    pub output: Output,
+    pub status: String,
+    pub prompt: String,
+    pub editor: Editor,
+    /// hides what is typed, for passwords
+    pub secret: bool,
+    /// shown dimmed after the input line, for what could be typed next
+    pub hint: Option<String>,
+    /// how many rows the view is scrolled up from the newest output
+    scroll: usize,
+    search: Option<Search>,
+    width: u16,
+    height: u16,
+    active: bool,
+}
+
+impl Screen {
+    /// Takes over the terminal: raw mode on the alternate screen, so the shell's contents come back on `leave`
+    pub fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        if let Err(e) = execute!(stdout(), EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+        let (width, height) = size()?;
+        Ok(Self {
+            output: Output::new(),
+            status: String::new(),
+            prompt: String::new(),
+            editor: Editor::default(),
+            secret: false,
+            hint: None,
+            scroll: 0,
+            search: None,
+            width,
+            height,
+            active: true,
+        })
+    }
+
+    /// Gives the terminal back as it was before `enter`
+    pub fn leave(&mut self) {
+        if self.active {
+            let _ = execute!(stdout(), LeaveAlternateScreen, Show);
+            let _ = disable_raw_mode();
+            self.active = false;
+        }
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// The size of the output pane
+    pub fn output_size(&self) -> (u16, u16) {
+        (self.width, self.height.saturating_sub(2))
+    }
+
+    /// Adds output, keeping the view where it is if it is scrolled up
+    pub fn push(&mut self, text: &str, style: Style) {
+        let width = self.width as usize;
+        let before = if self.scroll > 0 { self.output.total_rows(width) } else { 0 };
+        self.output.push(text, style);
+        if self.scroll > 0 {
+            self.scroll += self.output.total_rows(width).saturating_sub(before);
+            self.clamp_scroll();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.output.clear();
+        self.scroll = 0;
+    }
+
+    fn clamp_scroll(&mut self) {
+        let (width, rows) = self.output_size();
+        let max = self.output.total_rows(width as usize).saturating_sub(rows as usize);
+        self.scroll = self.scroll.min(max);
+    }
+
+    /// Scrolls by whole pages, up for positive `pages`
+    pub fn page(&mut self, pages: isize) {
+        let page = (self.output_size().1 as usize).saturating_sub(1).max(1);
+        self.scroll = if pages >= 0 {
+            self.scroll + page * pages as usize
+        } else {
+            self.scroll.saturating_sub(page * pages.unsigned_abs())
+        };
+        self.clamp_scroll();
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = usize::MAX;
+        self.clamp_scroll();
+    }
+
+    /// Highlights `term` while it is typed, showing its newest match
+    pub fn preview_search(&mut self, term: &str) {
+        if term.is_empty() {
+            self.clear_search();
+            return;
+        }
+        if let Some(found) = self.output.find(term, self.width as usize, None) {
+            self.scroll = found;
+            self.clamp_scroll();
+        }
+        self.search = Some(Search { term: term.to_string(), found_at: None });
+    }
+
+    /// Shows the next older match of `term`, or its newest match if `term` is a new search
+    pub fn search(&mut self, term: &str) -> Result<(), String> {
+        let after = match &self.search {
+            Some(search) if search.term == term => search.found_at,
+            _ => None,
+        };
+        match self.output.find(term, self.width as usize, after) {
+            Some(found) => {
+                self.scroll = found;
+                self.clamp_scroll();
+                self.search = Some(Search { term: term.to_string(), found_at: Some(found) });
+                Ok(())
+            }
+            None if after.is_some() => Err(format!("No older matches for '{}'", term)),
+            None => {
+                self.search = None;
+                Err(format!("No matches for '{}'", term))
+            }
+        }
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Redraws the whole screen
+    pub fn render(&self) -> io::Result<()> {
+        if !self.active {
+            return Ok(());
+        }
+        let mut out = stdout();
+        let width = self.width as usize;
+        let (_, rows) = self.output_size();
+        let reset = Style::default().overwrite();
+
+        queue!(out, Hide)?;
+        let highlight = self.search.as_ref().map(|search| search.term.as_str());
+        let lines = self.output.rows(width, rows as usize, self.scroll, highlight);
+        for row in 0..rows {
+            queue!(out, MoveTo(0, row), Clear(ClearType::CurrentLine))?;
+            if let Some(line) = lines.get(row as usize) {
+                for span in line.spans.iter() {
+                    // overwrite, so attributes of the span before don't carry over
+                    queue!(out, Print(span.style.overwrite()), Print(span.text.as_str()))?;
+                }
+                queue!(out, Print(reset))?;
+            }
+        }
+
+        // the status line is inverted across the whole width
+        let mut status = self.status.clone();
+        if let Some(search) = &self.search {
+            status.push_str(format!(" | search: {} (Esc to clear)", search.term).as_str());
+        }
+        if self.scroll > 0 {
+            status.push_str(format!(" | {} rows back (PgDn, Ctrl+End)", self.scroll).as_str());
+        }
+        let status = format!("{:<width$}", status.chars().take(width).collect::<String>(), width = width);
+        queue!(out, MoveTo(0, rows), Print(Style::default().invert()), Print(status), Print(reset))?;
+
+        // the input line scrolls sideways so the cursor stays on screen
+        let (line, cursor) = self.editor.view(self.prompt.as_str(), self.secret);
+        let skip = cursor.saturating_sub(width.saturating_sub(1));
+        let visible = line.chars().skip(skip).take(width).collect::<String>();
+        queue!(out, MoveTo(0, rows + 1), Clear(ClearType::CurrentLine), Print(visible.as_str()))?;
+        if let Some(hint) = &self.hint {
+            let room = width.saturating_sub(visible.chars().count());
+            let hint = hint.chars().take(room).collect::<String>();
+            queue!(out, Print(Style::default().dim()), Print(hint), Print(reset))?;
+        }
+        queue!(out, MoveTo((cursor - skip) as u16, rows + 1), Show)?;
+        out.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
@@ -0,0 +1,82 @@
+use better_term::style::{Color, Style};
+
+/// Reads a color: a name like `red` or `bright_blue`, a number from the 256 color palette, or `#rrggbb`
+pub fn parse_color(spec: &str) -> Result<Color, String> {
+    if let Some(hex) = spec.strip_prefix('#') {
+        return match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => Ok(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+            _ => Err(format!("'{}' is not a #rrggbb color", spec)),
+        };
+    }
+    if let Ok(index) = spec.parse::<u8>() {
+        return Ok(Color::Fixed(index));
+    }
+
+    let name = spec.to_ascii_lowercase().replace(['_', '-'], "");
+    Ok(match name.as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" | "magenta" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "brightblack" | "gray" | "grey" => Color::BrightBlack,
+        "brightred" => Color::BrightRed,
+        "brightgreen" => Color::BrightGreen,
+        "brightyellow" => Color::BrightYellow,
+        "brightblue" => Color::BrightBlue,
+        "brightpurple" | "brightmagenta" => Color::BrightPurple,
+        "brightcyan" => Color::BrightCyan,
+        "brightwhite" => Color::BrightWhite,
+        _ => return Err(format!("Unknown color '{}'", spec)),
+    })
+}
+
+/// Changes `style` by one word: an attribute like `bold`, a color for the text, or `bg:` and a color for the background
+pub fn apply_word(style: Style, word: &str) -> Result<Style, String> {
+    Ok(match word.to_ascii_lowercase().as_str() {
+        "plain" | "reset" => Style::default(),
+        "bold" => style.bold(),
+        "dim" => style.dim(),
+        "italic" => style.italic(),
+        "underline" => style.underline(),
+        "blink" => style.blink(),
+        "invert" => style.invert(),
+        "hide" => style.hide(),
+        "strikethrough" => style.strikethrough(),
+        _ => match word.split_once(':') {
+            Some((kind, color)) if kind.eq_ignore_ascii_case("bg") => style.bg(parse_color(color)?),
+            Some((kind, color)) if kind.eq_ignore_ascii_case("fg") => style.fg(parse_color(color)?),
+            Some(_) => return Err(format!("Unknown style '{}'", word)),
+            None => style.fg(parse_color(word).map_err(|_| format!("Unknown style '{}'", word))?),
+        },
+    })
+}
+
+/// Reads a style given as words joined by `+`, e.g. `bold+red+bg:#202020`
+pub fn parse_style(spec: &str) -> Result<Style, String> {
+    spec.split('+')
+        .filter(|word| !word.is_empty())
+        .try_fold(Style::default(), apply_word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_are_read_from_words() {
+        let style = parse_style("bold+bright_red+bg:#102030").unwrap();
+        assert!(style.bold);
+        assert!(style.fg == Some(Color::BrightRed));
+        assert!(style.bg == Some(Color::RGB(0x10, 0x20, 0x30)));
+        assert!(parse_style("fg:208").unwrap().fg == Some(Color::Fixed(208)));
+        assert!(parse_style("").unwrap() == Style::default());
+
+        assert_eq!(parse_style("bold+sparkly").err().unwrap(), "Unknown style 'sparkly'");
+        assert_eq!(parse_style("bg:#12").err().unwrap(), "'#12' is not a #rrggbb color");
+        assert_eq!(parse_style("bg:mauve").err().unwrap(), "Unknown color 'mauve'");
+    }
+}
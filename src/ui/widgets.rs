@@ -0,0 +1,191 @@
+use better_term::style::{Color, Style};
+use crate::ui::output::Line;
+
+/// How wide a bar is when the server doesn't say
+pub const DEFAULT_BAR_WIDTH: u16 = 20;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+fn width_of(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Cuts `text` to at most `width` characters, marking the cut with an ellipsis
+fn fit(text: &str, width: usize) -> String {
+    if width_of(text) <= width {
+        return text.to_string();
+    }
+    let mut cut = text.chars().take(width.saturating_sub(1)).collect::<String>();
+    if width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let space = width.saturating_sub(width_of(text));
+    let (left, right) = match align {
+        Align::Left => (0, space),
+        Align::Right => (space, 0),
+        Align::Center => (space / 2, space - space / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+fn line(text: &str, style: Style) -> Line {
+    let mut line = Line::default();
+    line.push(text, style);
+    line
+}
+
+/// A border around `lines` with `title` in its top edge, at most `width` wide
+pub fn boxed(title: &str, lines: &[String], width: usize) -> Vec<Line> {
+    let room = width.saturating_sub(4).max(1);
+    let title = fit(title, room.saturating_sub(2));
+    let lines = lines.iter().map(|text| fit(text, room)).collect::<Vec<String>>();
+    let titled = if title.is_empty() { 0 } else { width_of(title.as_str()) + 2 };
+    let inner = lines.iter().map(|text| width_of(text)).max().unwrap_or(0).max(titled);
+
+    let mut boxed = Vec::with_capacity(lines.len() + 2);
+    let mut top = Line::default();
+    if title.is_empty() {
+        top.push(format!("┌{}┐", "─".repeat(inner + 2)).as_str(), Style::default());
+    } else {
+        top.push("┌─ ", Style::default());
+        top.push(title.as_str(), Style::default().bold());
+        top.push(format!(" {}┐", "─".repeat(inner - titled + 1)).as_str(), Style::default());
+    }
+    boxed.push(top);
+    for text in lines.iter() {
+        boxed.push(line(format!("│ {} │", pad(text, inner, Align::Left)).as_str(), Style::default()));
+    }
+    boxed.push(line(format!("└{}┘", "─".repeat(inner + 2)).as_str(), Style::default()));
+    boxed
+}
+
+/// A table with a bold header and aligned columns. Cells are separated by `|`, and a header
+/// starting with `>` right aligns its column, one starting with `^` centers it.
+pub fn table(columns: &str, rows: &[String]) -> Vec<Line> {
+    let columns = columns.split('|').map(|header| match header.chars().next() {
+        Some('>') => (&header[1..], Align::Right),
+        Some('^') => (&header[1..], Align::Center),
+        _ => (header, Align::Left),
+    }).collect::<Vec<(&str, Align)>>();
+    // missing cells are left empty, extra ones are dropped
+    let rows = rows.iter().map(|row| {
+        let mut cells = row.split('|').collect::<Vec<&str>>();
+        cells.resize(columns.len(), "");
+        cells
+    }).collect::<Vec<Vec<&str>>>();
+
+    let widths = columns.iter().enumerate().map(|(i, (header, _))| {
+        rows.iter().map(|cells| width_of(cells[i])).chain(Some(width_of(header))).max().unwrap_or(0)
+    }).collect::<Vec<usize>>();
+
+    let format_row = |cells: Vec<&str>| {
+        cells.iter().zip(columns.iter()).zip(widths.iter())
+            .map(|((cell, (_, align)), width)| pad(cell, *width, *align))
+            .collect::<Vec<String>>()
+            .join(" │ ")
+    };
+
+    let mut table = Vec::with_capacity(rows.len() + 2);
+    table.push(line(format_row(columns.iter().map(|(header, _)| *header).collect()).as_str(), Style::default().bold()));
+    table.push(line(widths.iter().map(|width| "─".repeat(*width)).collect::<Vec<String>>().join("─┼─").as_str(), Style::default()));
+    for cells in rows {
+        table.push(line(format_row(cells).as_str(), Style::default()));
+    }
+    table
+}
+
+/// A progress or health bar like `HP [██████░░░░] 60/100`. Without a color it goes
+/// from green to yellow to red as it empties.
+pub fn bar(label: &str, value: f64, max: f64, width: usize, color: Option<Color>) -> Line {
+    let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+    let filled = (ratio * width as f64).round() as usize;
+    let color = color.unwrap_or(if ratio > 0.5 {
+        Color::Green
+    } else if ratio > 0.25 {
+        Color::Yellow
+    } else {
+        Color::Red
+    });
+
+    let mut bar = Line::default();
+    if !label.is_empty() {
+        bar.push(format!("{} ", label).as_str(), Style::default());
+    }
+    bar.push("[", Style::default());
+    bar.push("█".repeat(filled).as_str(), color.to_style_fg());
+    bar.push("░".repeat(width - filled).as_str(), Style::default().dim());
+    bar.push(format!("] {}/{}", value, max).as_str(), Style::default());
+    bar
+}
+
+/// A horizontal rule across `width`, with `title` near its start
+pub fn rule(title: &str, width: usize) -> Line {
+    if title.is_empty() {
+        return line("─".repeat(width).as_str(), Style::default().dim());
+    }
+    let title = fit(title, width.saturating_sub(4));
+    let mut rule = Line::default();
+    rule.push("── ", Style::default().dim());
+    rule.push(title.as_str(), Style::default().bold());
+    rule.push(format!(" {}", "─".repeat(width.saturating_sub(width_of(title.as_str()) + 4))).as_str(), Style::default().dim());
+    rule
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.spans.iter().map(|span| span.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn boxes_fit_their_contents() {
+        let lines = vec!["Sword".to_string(), "Shield of the north".to_string()];
+        assert_eq!(texts(&boxed("Inventory", &lines, 80)), vec![
+            "┌─ Inventory ─────────┐",
+            "│ Sword               │",
+            "│ Shield of the north │",
+            "└─────────────────────┘",
+        ]);
+        // too long lines are cut to the width
+        assert_eq!(texts(&boxed("", &lines, 12)), vec![
+            "┌──────────┐",
+            "│ Sword    │",
+            "│ Shield … │",
+            "└──────────┘",
+        ]);
+    }
+
+    #[test]
+    fn tables_align_their_columns() {
+        let rows = vec!["Bob|12|Mage".to_string(), "Alexandra|7".to_string()];
+        assert_eq!(texts(&table("Name|>Level|^Class", &rows)), vec![
+            "Name      │ Level │ Class",
+            "──────────┼───────┼──────",
+            "Bob       │    12 │ Mage ",
+            "Alexandra │     7 │      ",
+        ]);
+    }
+
+    #[test]
+    fn bars_and_rules_fill_their_width() {
+        let hp = bar("HP", 60.0, 100.0, 10, None);
+        assert_eq!(texts(std::slice::from_ref(&hp)), vec!["HP [██████░░░░] 60/100"]);
+        assert!(hp.spans[1].style.fg == Some(Color::Green));
+        assert!(bar("", 1.0, 10.0, 4, None).spans[1].style.fg == Some(Color::Red));
+        assert_eq!(texts(&[bar("", 150.0, 100.0, 4, None)]), vec!["[████] 150/100"]);
+
+        assert_eq!(texts(&[rule("", 5)]), vec!["─────"]);
+        assert_eq!(texts(&[rule("Map", 12)]), vec!["── Map ─────"]);
+    }
+}
@@ -1,7 +1,6 @@
 use std::time::{UNIX_EPOCH, SystemTime, Duration};
-use std::path::Path;
-use chrono::Local;
-use chrono::format::{DelayedFormat, StrftimeItems};
+use std::path::PathBuf;
+use std::fs;
 
 pub fn systime() -> Duration {
     SystemTime::now()
@@ -19,4 +18,16 @@ pub fn unwrap_or_default<T>(opt: Option<T>, default: T) -> T {
         Some(t) => t,
         None => default
     }
-}
\ No newline at end of file
+}
+
+/// Gets the directory client data (server list, etc.) is stored in, creating it if needed
+pub fn data_dir() -> Result<PathBuf, String> {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mini_mmo_client");
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Could not create the client data directory {}: {}", dir.display(), e))?;
+    }
+    Ok(dir)
+}
@@ -0,0 +1,110 @@
+//! Uses the library's `Client` the way a bot would, against an in-process mock server.
+
+use std::net::TcpListener;
+use std::time::Duration;
+use mini_mmo_client::client::Client;
+use mini_mmo_client::mock_server::{Fixture, FixtureUser, MockServer, Reply, ScriptStep};
+use mini_mmo_client::network::error::NetworkError;
+use mini_mmo_client::network::handshake::HandshakeError;
+use mini_mmo_client::network::login_data::LoginData;
+use mini_mmo_client::network::server_event::ServerEvent;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn start_server(fixture: Fixture) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    MockServer::new(fixture).spawn(listener);
+    port
+}
+
+fn fixture(script: Vec<ScriptStep>) -> Fixture {
+    Fixture {
+        users: vec![FixtureUser { username: "bot".to_string(), password: "beep".to_string() }],
+        script,
+        replies: vec![Reply { input: "ping".to_string(), message: "pong".to_string(), data: "println pong".to_string() }],
+        ..Fixture::default()
+    }
+}
+
+fn login_data(passwd: &str) -> LoginData {
+    LoginData { email: String::new(), username: "bot".to_string(), passwd: passwd.to_string(), signup: false }
+}
+
+fn next_event(client: &Client) -> ServerEvent {
+    client.next_event_timeout(TIMEOUT).expect("timed out waiting for an event").unwrap()
+}
+
+#[test]
+fn login_send_and_receive() {
+    let port = start_server(fixture(vec![ScriptStep::Keepalive, ScriptStep::Message { message: "hello".to_string(), data: String::new() }]));
+    let mut client = Client::connect("127.0.0.1", port, None).unwrap();
+    assert!(!client.is_logged_in());
+
+    match client.login(login_data("boop")) {
+        Err(HandshakeError::LoginRejected(_)) => {}
+        _ => panic!("the wrong password should be rejected"),
+    }
+    let logged_in = client.login(login_data("beep")).unwrap();
+    assert_eq!(logged_in.motd, Fixture::default().motd);
+    assert!(client.is_logged_in());
+    assert!(client.resume_token().is_some());
+
+    // the keepalive is answered in the background and skipped
+    match next_event(&client) {
+        ServerEvent::Message(msg) => assert_eq!(msg.message, "hello"),
+        _ => panic!("expected the scripted message"),
+    }
+
+    client.send("ping", "").unwrap();
+    match next_event(&client) {
+        ServerEvent::Message(msg) => {
+            assert_eq!(msg.message, "pong");
+            assert_eq!(msg.data, "println pong");
+        }
+        _ => panic!("expected the reply"),
+    }
+    assert!(client.try_next_event().is_none());
+}
+
+#[test]
+fn logout_is_acknowledged() {
+    let port = start_server(fixture(Vec::new()));
+    let mut client = Client::connect("127.0.0.1", port, None).unwrap();
+    client.login(login_data("beep")).unwrap();
+
+    assert!(client.logout("done for today", TIMEOUT).unwrap());
+    assert!(!client.is_logged_in());
+
+    // the acknowledgement is waited for on forwarded events too
+    let mut client = Client::connect("127.0.0.1", port, None).unwrap();
+    client.login(login_data("beep")).unwrap();
+    let (events_tx, events) = std::sync::mpsc::channel::<Result<ServerEvent, NetworkError>>();
+    client.forward_events(events_tx);
+    assert!(client.logout("done for today", TIMEOUT).unwrap());
+    match events.recv_timeout(TIMEOUT).unwrap() {
+        Ok(ServerEvent::Disconnected(reason)) => assert_eq!(reason, "Goodbye!"),
+        _ => panic!("expected the acknowledgement"),
+    }
+}
+
+#[test]
+fn server_disconnect_is_reported() {
+    let port = start_server(fixture(vec![ScriptStep::Disconnect { reason: "maintenance".to_string() }]));
+    let mut client = Client::connect("127.0.0.1", port, None).unwrap();
+    client.login(login_data("beep")).unwrap();
+
+    match next_event(&client) {
+        ServerEvent::Disconnected(reason) => assert_eq!(reason, "maintenance"),
+        _ => panic!("expected a disconnect"),
+    }
+}
+
+#[test]
+fn outdated_client_is_rejected() {
+    let port = start_server(Fixture { version: "0.0.1".to_string(), ..fixture(Vec::new()) });
+    match Client::connect("127.0.0.1", port, None) {
+        Err(HandshakeError::Outdated { server_version }) => assert_eq!(server_version, "0.0.1"),
+        _ => panic!("the version check should fail"),
+    }
+}
@@ -0,0 +1,478 @@
+//! Drives the terminal client binary through whole sessions against an in-process mock server.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use mini_mmo_client::mock_server::{Fixture, FixtureUser, MockServer, ScriptStep};
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+static NEXT_CLIENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts a mock server on an ephemeral port
+fn start_server(fixture: Fixture) -> (MockServer, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = MockServer::new(fixture);
+    server.spawn(listener);
+    (server, port)
+}
+
+fn fixture(script: Vec<ScriptStep>) -> Fixture {
+    Fixture {
+        users: vec![FixtureUser { username: "demo".to_string(), password: "demo".to_string() }],
+        script,
+        ..Fixture::default()
+    }
+}
+
+/// Waits until `check` passes, failing the test with `describe()` after the timeout
+fn wait_until<F: Fn() -> bool, D: Fn() -> String>(check: F, describe: D) {
+    let start = Instant::now();
+    while !check() {
+        if start.elapsed() > TIMEOUT {
+            panic!("timed out, {}", describe());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// The client binary, with its own data directory that only knows the test server
+struct TestClient {
+    child: Child,
+    stdin: Option<Box<dyn Write>>,
+    /// what ends a line: a terminal sends a carriage return for the enter key
+    enter: &'static str,
+    output: Arc<Mutex<String>>,
+    data_dir: PathBuf,
+}
+
+impl TestClient {
+    fn start(port: u16) -> Self {
+        let data_dir = client_data_dir(port);
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mini_mmo_client"))
+            .env("XDG_DATA_HOME", &data_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let output = Arc::new(Mutex::new(String::new()));
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        collect(stdout, output.clone());
+        collect(stderr, output.clone());
+
+        let stdin = child.stdin.take().map(|stdin| Box::new(stdin) as Box<dyn Write>);
+        Self { stdin, enter: "\n", child, output, data_dir }
+    }
+
+    /// Runs the client on a pseudo terminal, so it starts the full-screen interface
+    #[cfg(unix)]
+    fn start_in_terminal(port: u16) -> Self {
+        use std::os::unix::io::FromRawFd;
+
+        let data_dir = client_data_dir(port);
+        let (mut master, mut slave) = (0, 0);
+        let size = libc::winsize { ws_row: 24, ws_col: 80, ws_xpixel: 0, ws_ypixel: 0 };
+        let opened = unsafe { libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null_mut(), &size) };
+        assert_eq!(opened, 0, "failed to open a pseudo terminal");
+        let (master, slave) = unsafe { (fs::File::from_raw_fd(master), fs::File::from_raw_fd(slave)) };
+
+        let child = Command::new(env!("CARGO_BIN_EXE_mini_mmo_client"))
+            .env("XDG_DATA_HOME", &data_dir)
+            .stdin(slave.try_clone().unwrap())
+            .stdout(slave.try_clone().unwrap())
+            .stderr(slave)
+            .spawn()
+            .unwrap();
+
+        // reading fails once the client has closed the terminal, which ends the collection
+        let output = Arc::new(Mutex::new(String::new()));
+        collect(master.try_clone().unwrap(), output.clone());
+
+        Self { stdin: Some(Box::new(master)), enter: "\r", child, output, data_dir }
+    }
+
+    /// Sends `keys` as they are, without ending the line
+    fn type_keys(&mut self, keys: &str) {
+        let stdin = self.stdin.as_mut().expect("input already closed");
+        stdin.write_all(keys.as_bytes()).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn send(&mut self, line: &str) {
+        let line = format!("{}{}", line, self.enter);
+        self.type_keys(line.as_str());
+    }
+
+    fn send_all(&mut self, lines: &[&str]) {
+        for line in lines {
+            self.send(line);
+        }
+    }
+
+    fn close_input(&mut self) {
+        self.stdin.take();
+    }
+
+    fn output(&self) -> String {
+        self.output.lock().unwrap().clone()
+    }
+
+    fn wait_for(&self, text: &str) {
+        wait_until(|| self.output().contains(text), || format!("waiting for {:?} in the output:\n{}", text, self.output()));
+    }
+
+    fn wait_exit(&mut self) -> ExitStatus {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            if start.elapsed() > TIMEOUT {
+                panic!("the client did not exit, output:\n{}", self.output());
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+/// A data directory whose server list only has the test server, as the default
+fn client_data_dir(port: u16) -> PathBuf {
+    let data_dir = std::env::temp_dir().join(format!("mini_mmo_client_test_{}_{}", std::process::id(), NEXT_CLIENT.fetch_add(1, Ordering::SeqCst)));
+    let _ = fs::remove_dir_all(&data_dir);
+    let app_dir = data_dir.join("mini_mmo_client");
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::write(app_dir.join("servers.toml"), format!("default = \"test\"\n\n[[server]]\nname = \"test\"\nip = \"127.0.0.1\"\nport = {}\n", port)).unwrap();
+    data_dir
+}
+
+fn collect<R: Read + Send + 'static>(mut source: R, output: Arc<Mutex<String>>) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = source.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output.lock().unwrap().push_str(String::from_utf8_lossy(&buf[..n]).as_ref());
+        }
+    });
+}
+
+#[test]
+fn outdated_client_is_told_to_update() {
+    let (_server, port) = start_server(Fixture { version: "0.0.1".to_string(), ..fixture(Vec::new()) });
+    let mut client = TestClient::start(port);
+
+    // pick the default server
+    client.send("");
+    client.wait_for("Your client is outdated! The server is running 0.0.1");
+    client.wait_exit();
+}
+
+#[test]
+fn login_shows_the_motd() {
+    let (_server, port) = start_server(Fixture { motd: "Hello from the test server".to_string(), ..fixture(Vec::new()) });
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!\nHello from the test server");
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn failed_login_can_be_retried() {
+    let (server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "wrong"]);
+    client.wait_for("Login Attempt Failed.\nWrong username or password");
+    client.send_all(&["n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+    assert!(server.log().iter().any(|line| line == "Logged in demo"));
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn signup_asks_again_when_the_passwords_do_not_match() {
+    let (server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "y", "new@example.com", "newbie", "first", "second"]);
+    client.wait_for("The passwords did not match!");
+    client.send_all(&["secret", "secret"]);
+    client.wait_for("Logged in successfully!");
+    assert!(server.log().iter().any(|line| line == "Signed up newbie"));
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn commands_in_message_data_are_run() {
+    let data = r#"println first command;println second command;println "  quoted; with \"escapes\"""#;
+    let script = vec![ScriptStep::Message { message: "Incoming commands\n".to_string(), data: data.to_string() }];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Incoming commands\n");
+    client.wait_for("first command\n");
+    client.wait_for("second command\n");
+    client.wait_for("  quoted; with \"escapes\"\n");
+
+    // messages the user sends are answered too
+    client.send("look around");
+    client.wait_for("You said: look around");
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[cfg(unix)]
+#[test]
+fn advertised_commands_are_completed_and_hinted() {
+    let data = r#"commands look "say [words: text...]""#;
+    let script = vec![ScriptStep::Message { message: "Welcome aboard\n".to_string(), data: data.to_string() }];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start_in_terminal(port);
+
+    // the login reads the terminal itself, so every answer waits for its question
+    client.wait_for("> ");
+    client.send("");
+    client.wait_for("signing up?");
+    client.send("n");
+    client.wait_for("username: ");
+    client.send("demo");
+    client.wait_for("password: ");
+    // the password is read in raw mode, which is only switched on after the prompt is shown
+    thread::sleep(Duration::from_millis(200));
+    client.send("demo");
+    client.wait_for("Welcome aboard");
+
+    // a command the server advertised is completed, and its arguments are hinted
+    client.type_keys("sa\t");
+    client.wait_for("[words: text...]");
+    client.send("hello");
+    client.wait_for("You said: say hello");
+
+    // as are the client's own commands
+    client.type_keys("/recon\t");
+    client.send("");
+    client.wait_for("Session resumed!");
+    client.send("/quit");
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn screens_are_drawn_from_commands() {
+    let data = r#"ui 3;box Inventory Sword "Shield of the north";table "Name|>Level" "Bob|12" "Alexandra|7";bar HP 60 100 10;bar MP NaN inf;span sparkly nothing"#;
+    let script = vec![ScriptStep::Message { message: String::new(), data: data.to_string() }];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("The server uses version 3 of the screen commands, this client knows version 2");
+    client.wait_for("│ Shield of the north │");
+    client.wait_for("Alexandra │     7");
+    client.wait_for("██████");
+    client.wait_for("Can't draw the bar 'MP' at NaN of inf, it needs finite numbers and a max above 0");
+    client.wait_for("Unknown style 'sparkly'");
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn message_markup_falls_back_to_plain_text() {
+    // the output isn't a terminal, so the markup is dropped rather than drawn in color
+    let message = "{bold}{red}Danger{reset} ahead \x1b[2J\x1b]0;title\x07{{braces} {sparkly}\n";
+    let script = vec![ScriptStep::Message { message: message.to_string(), data: String::new() }];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Danger ahead {braces} {sparkly}\n");
+    client.send("/log 1");
+    client.wait_for("> Danger ahead {braces} {sparkly}\n");
+    let session = client.output().split_off(client.output().find("Logged in").unwrap());
+    assert!(!session.contains('\x1b'), "escapes in the session: {:?}", session);
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn server_commands_are_sandboxed() {
+    // servers may not resize the terminal unless their entry allows it, and a message runs at most 50 commands
+    let flood = vec!["println flood"; 52].join(";");
+    let script = vec![
+        ScriptStep::Message { message: String::new(), data: "size 10 10;println after the resize".to_string() },
+        ScriptStep::Message { message: String::new(), data: flood },
+    ];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Blocked a server command: The command 'size' was blocked, it is denied for this server");
+    client.wait_for("after the resize");
+    client.wait_for("Blocked a server command: 2 more commands in the message were dropped, it may only run 50");
+    assert_eq!(client.output().matches("flood").count(), 50);
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn local_commands_stay_on_the_client() {
+    let (server, port) = start_server(fixture(vec![ScriptStep::Message { message: "Welcome aboard\n".to_string(), data: String::new() }]));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Welcome aboard");
+
+    client.send("/help");
+    client.wait_for("/log [lines: u32]");
+    client.send("/log 1");
+    client.wait_for("clears the search without text\n> Welcome aboard\n");
+    client.send("/nope");
+    client.wait_for("The command '/nope' does not exist!");
+
+    // a doubled prefix sends the line with a single one
+    client.send("//shrug");
+    client.wait_for("You said: /shrug");
+
+    client.send("/reconnect");
+    client.wait_for("Session resumed!");
+    client.send("/quit");
+    assert!(client.wait_exit().success());
+    assert_eq!(server.log().iter().filter(|line| line.starts_with("Logged in")).count(), 1);
+}
+
+#[test]
+fn local_commands_are_described() {
+    let (_server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+
+    // the listing is grouped by category, with a line for each command
+    client.send("/help");
+    client.wait_for("Connection:\n  /quit [reason: text...] - Logs out and closes the client, telling the server why\n");
+    client.wait_for("Output:\n  /clear - Clears the output\n  /log [lines: u32] - Shows the last lines the server sent\n");
+
+    // a single command is explained, with or without its prefix
+    client.send("/help log");
+    client.wait_for("/log [lines: u32]\n  Shows the last lines the server sent\n");
+    client.send("/help /reconnect");
+    client.wait_for("/reconnect\n  Drops the connection and resumes the session over a new one\n");
+
+    // close matches are suggested for unknown commands
+    client.send("/qiut");
+    client.wait_for("The command '/qiut' does not exist! Did you mean '/quit'?");
+    client.send("/help serves");
+    client.wait_for("The command '/serves' does not exist! Did you mean '/servers'?");
+
+    client.send("/quit");
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn reconnecting_settles_on_the_new_connection() {
+    let (server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+    client.send("/reconnect");
+    client.wait_for("Session resumed!");
+
+    // closing the old connection must not look like the new one was lost
+    client.send("still here");
+    client.wait_for("You said: still here");
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(client.output().matches("Reconnecting in").count(), 1, "output:\n{}", client.output());
+    assert!(!client.output().contains("The connection was closed"), "output:\n{}", client.output());
+
+    client.send("/quit");
+    assert!(client.wait_exit().success());
+    assert_eq!(server.log().iter().filter(|line| line.ends_with("] connected")).count(), 2, "server log: {:?}", server.log());
+}
+
+#[test]
+fn quitting_logs_out_with_a_reason() {
+    let (server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+    client.send("/quit see you later");
+    assert!(client.wait_exit().success());
+    assert!(server.log().iter().any(|line| line == "Client left: see you later"), "server log: {:?}", server.log());
+    assert!(!client.output().contains("The server did not answer"));
+}
+
+#[cfg(unix)]
+#[test]
+fn sigterm_logs_out() {
+    let (server, port) = start_server(fixture(Vec::new()));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+    let killed = Command::new("kill").arg("-TERM").arg(client.child.id().to_string()).status().unwrap();
+    assert!(killed.success());
+    client.wait_for("Logging out...");
+    assert!(client.wait_exit().success());
+    assert!(server.log().iter().any(|line| line == "Client left: The client was closed"), "server log: {:?}", server.log());
+}
+
+#[test]
+fn keepalives_are_answered() {
+    let (server, port) = start_server(fixture(vec![ScriptStep::Keepalive]));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Logged in successfully!");
+    wait_until(|| server.log().iter().any(|line| line == "Keepalive answered"), || format!("waiting for the keepalive to be answered, server log: {:?}", server.log()));
+
+    client.close_input();
+    assert!(client.wait_exit().success());
+}
+
+#[test]
+fn server_disconnect_ends_the_session() {
+    let script = vec![
+        ScriptStep::Message { message: "Closing soon\n".to_string(), data: String::new() },
+        ScriptStep::Disconnect { reason: "The server is shutting down".to_string() },
+    ];
+    let (_server, port) = start_server(fixture(script));
+    let mut client = TestClient::start(port);
+
+    client.send_all(&["", "n", "demo", "demo"]);
+    client.wait_for("Closing soon");
+    client.wait_for("The server is shutting down");
+    client.wait_for("You have been disconnected.");
+    assert!(client.wait_exit().success());
+}